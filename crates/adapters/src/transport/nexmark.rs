@@ -3,9 +3,10 @@
 use std::collections::VecDeque;
 use std::io::Cursor;
 use std::mem;
-use std::sync::{atomic::Ordering, Arc, Mutex};
+use std::sync::{atomic::Ordering, Arc, Condvar, Mutex};
 use std::sync::{Barrier, OnceLock, Weak};
 use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
 
 use crate::format::InputBuffer;
 use crate::{
@@ -19,8 +20,11 @@ use dbsp_nexmark::model::Event;
 use dbsp_nexmark::{config::GeneratorOptions, generator::config::Config as GeneratorConfig};
 use enum_map::EnumMap;
 use feldera_types::program_schema::Relation;
-use feldera_types::transport::nexmark::{NexmarkInputConfig, NexmarkInputOptions, NexmarkTable};
-use rand::rngs::ThreadRng;
+use feldera_types::transport::nexmark::{
+    NexmarkGenerationMode, NexmarkInputConfig, NexmarkInputOptions, NexmarkTable,
+};
+use log::info;
+use rand::{rngs::StdRng, SeedableRng};
 use rmpv::Value as RmpValue;
 
 use super::{InputReaderCommand, NonFtInputReaderCommand};
@@ -93,6 +97,9 @@ impl InputGenerator {
                 let Some(buffers) = self.inner.buffers.lock().unwrap().pop_front() else {
                     break;
                 };
+                // A slot just opened up: wake any generator thread parked in
+                // `push_bounded` waiting for room.
+                self.inner.buffers_not_full.notify_all();
                 for mut buffer in buffers {
                     total += buffer.flush_all();
                 }
@@ -116,6 +123,57 @@ impl InputReader for InputGenerator {
     }
 }
 
+/// Paces event generation against wall-clock time, either at a fixed
+/// `events_per_second` or at a multiple of the synthetic event time
+/// (`speedup_factor`), so callers can benchmark under controlled load
+/// instead of only at max throughput.
+///
+/// Does nothing (never delays) unless one of the two options is configured.
+struct Pacer {
+    start: Instant,
+    first_wall_clock_timestamp: Option<i64>,
+    per_thread_events_per_second: Option<f64>,
+    speedup_factor: Option<f64>,
+    emitted: u64,
+}
+
+impl Pacer {
+    fn new(options: &NexmarkInputOptions) -> Self {
+        Self {
+            start: Instant::now(),
+            first_wall_clock_timestamp: None,
+            per_thread_events_per_second: options
+                .events_per_second
+                .map(|eps| eps as f64 / options.threads as f64),
+            speedup_factor: options.speedup_factor,
+            emitted: 0,
+        }
+    }
+
+    /// Returns how long to park before emitting the next event, if pacing is
+    /// configured and we're ahead of schedule. `wall_clock_timestamp` is the
+    /// synthetic event time (in milliseconds) attached to the event that was
+    /// just generated.
+    fn delay_for(&mut self, wall_clock_timestamp: i64) -> Option<Duration> {
+        self.emitted += 1;
+
+        let target = if let Some(rate) = self.per_thread_events_per_second {
+            Duration::from_secs_f64(self.emitted as f64 / rate)
+        } else if let Some(speedup) = self.speedup_factor {
+            let first = *self
+                .first_wall_clock_timestamp
+                .get_or_insert(wall_clock_timestamp);
+            let event_elapsed_ms = (wall_clock_timestamp - first).max(0) as f64;
+            Duration::from_secs_f64(event_elapsed_ms / 1000.0 / speedup)
+        } else {
+            return None;
+        };
+
+        let elapsed = self.start.elapsed();
+        target.checked_sub(elapsed)
+    }
+}
+
 static INNER: Mutex<Weak<Inner>> = Mutex::new(Weak::new());
 
 struct Inner {
@@ -142,6 +200,15 @@ struct Inner {
     threads: Mutex<Vec<Thread>>,
 
     buffers: Mutex<VecDeque<Vec<Box<dyn InputBuffer>>>>,
+
+    /// Signaled whenever `queue()` drains an entry from `buffers`, so a
+    /// generator thread blocked in `push_bounded` waiting for room can wake
+    /// up and recheck.
+    buffers_not_full: Condvar,
+
+    /// Set once the bounded backlog of a `SnapshotThenSubscribe` run has been
+    /// fully emitted, so only the first thread to cross the boundary logs it.
+    snapshot_complete: Atomic<bool>,
 }
 
 impl Inner {
@@ -153,6 +220,8 @@ impl Inner {
             consumers: Mutex::new(EnumMap::default()),
             threads: Mutex::new(Vec::new()),
             buffers: Mutex::new(VecDeque::new()),
+            buffers_not_full: Condvar::new(),
+            snapshot_complete: Atomic::new(false),
         });
         thread::Builder::new()
             .name(String::from("nexmark"))
@@ -233,6 +302,40 @@ impl Inner {
         }
     }
 
+    /// Pushes `buffers` onto `self.buffers`, blocking (parking the calling
+    /// generator thread) while the queue is already at `max_queued_batches`
+    /// capacity, so a slow consumer applies backpressure instead of letting
+    /// generator threads race arbitrarily far ahead and exhaust memory.
+    fn push_bounded(&self, buffers: Vec<Box<dyn InputBuffer>>, max_queued_batches: usize) {
+        let mut guard = self.buffers.lock().unwrap();
+        while guard.len() >= max_queued_batches {
+            if matches!(self.status(), PipelineState::Terminated) {
+                break;
+            }
+            guard = self.buffers_not_full.wait(guard).unwrap();
+        }
+        guard.push_back(buffers);
+    }
+
+    /// Adjusts `batch_size` so the next batch's wall-clock duration moves
+    /// toward `target`: halved if we overshot by more than 2x, doubled if we
+    /// undershot by more than 2x, left alone otherwise. Clamped to `[1,
+    /// 10_000_000]` so a pathological measurement (e.g. while paused) can't
+    /// collapse the batch size to zero or blow it up unboundedly.
+    fn calibrate_batch_size(batch_size: u64, elapsed: Duration, target: Duration) -> u64 {
+        const MIN_BATCH_SIZE: u64 = 1;
+        const MAX_BATCH_SIZE: u64 = 10_000_000;
+
+        let adjusted = if elapsed > target.saturating_mul(2) {
+            batch_size / 2
+        } else if elapsed.saturating_mul(2) < target {
+            batch_size.saturating_mul(2)
+        } else {
+            batch_size
+        };
+        adjusted.clamp(MIN_BATCH_SIZE, MAX_BATCH_SIZE)
+    }
+
     /// Returns a CSV writer with our style for `inner`.
     fn make_csv_writer(inner: Vec<u8>) -> CsvWriter<Cursor<Vec<u8>>> {
         CsvWriterBuilder::new()
@@ -287,9 +390,16 @@ impl Inner {
             handle.join().unwrap();
         }
 
-        // Input is exhausted.
-        for (_table, consumer) in consumers {
-            consumer.eoi();
+        // Input is exhausted, unless we're generating a live, unbounded feed,
+        // in which case the generator threads only stop on termination and
+        // there's no end-of-input to signal.
+        if !matches!(
+            options.mode,
+            NexmarkGenerationMode::Subscribe | NexmarkGenerationMode::SnapshotThenSubscribe
+        ) {
+            for (_table, consumer) in consumers {
+                consumer.eoi();
+            }
         }
     }
 
@@ -304,65 +414,173 @@ impl Inner {
     ) {
         let options = self.options.get().unwrap();
 
+        // `Subscribe`/`SnapshotThenSubscribe` generate an unbounded live feed:
+        // they only stop on termination, so there's no fixed number of
+        // batches to synchronize on and the generator itself must not cap
+        // `max_events`.
+        let unbounded = !matches!(options.mode, NexmarkGenerationMode::Snapshot);
+
         // Calculate the exact number of times to wait on `barrier`. If we wait
         // any fewer times than that, the other threads will get stuck (if we
         // wait more, we'll get stuck). It's harmless if it's greater than the
-        // number of batches.
-        let n_batches = options
+        // number of batches. Only meaningful for the bounded `Snapshot` mode.
+        // This starts from `batch_size_per_thread`, the initial value of the
+        // mutable `batch_size` below, and is recomputed from the live
+        // `batch_size` once `target_batch_duration` calibration moves it away
+        // from that starting point -- otherwise this count goes stale and
+        // either truncates the backlog (if batch_size shrinks) or leaves
+        // trailing empty batches (if it grows).
+        let mut n_batches = options
             .events
             .div_ceil(options.batch_size_per_thread * options.threads as u64);
 
+        // In `Subscribe` mode we skip the historical backlog entirely and
+        // start generating from "now", i.e. from the event id where the
+        // backlog would otherwise have ended.
+        let first_event_id = if matches!(options.mode, NexmarkGenerationMode::Subscribe) {
+            options.events
+        } else {
+            0
+        };
+
         let generator_options = GeneratorOptions {
-            max_events: options.events,
+            max_events: if unbounded { u64::MAX } else { options.events },
             num_event_generators: options.threads,
             ..GeneratorOptions::default()
         };
+        // When `seed` is configured, derive a distinct, reproducible seed per
+        // generator thread so the same configuration always produces the
+        // same stream of events; otherwise fall back to an OS-seeded RNG, as
+        // before.
+        let rng = match options.seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ index as u64),
+            None => StdRng::from_entropy(),
+        };
         let mut generator = NexmarkGenerator::new(
-            GeneratorConfig::new(generator_options, 0, 0, index),
-            ThreadRng::default(),
+            GeneratorConfig::new(generator_options, first_event_id, 0, index),
+            rng,
             0,
         );
 
         let mut buffers = EnumMap::from_fn(|_| Vec::new());
-
-        for i in 0..n_batches {
+        let mut pacer = Pacer::new(options);
+        let mut total_emitted: u64 = 0;
+
+        // `batch_size_per_thread` is just the starting point: if
+        // `target_batch_duration` is configured, we recalibrate it after
+        // every batch so each one takes roughly that long, trading off
+        // per-batch parsing/locking overhead against how promptly the
+        // consumer gets fed.
+        let mut batch_size = options.batch_size_per_thread;
+
+        // If the configured parser for a table is the adapter's own native
+        // format, we can feed it the already-constructed `Person`/`Auction`/
+        // `Bid` structs directly and skip the CSV serialize-then-parse round
+        // trip, which otherwise dominates CPU for the (92% bid) workload.
+        let native: EnumMap<NexmarkTable, bool> =
+            EnumMap::from_fn(|table| parsers[table].is_native_format());
+        let mut native_records: EnumMap<NexmarkTable, Vec<Box<dyn erased_serde::Serialize>>> =
+            EnumMap::from_fn(|_| Vec::new());
+
+        let mut i: u64 = 0;
+        while unbounded || i < n_batches {
             // Wait until we're ready to run.
             if self.wait_to_run().is_err() {
                 // Make sure we synchronize exactly `n_batches` times.
-                for _ in i..n_batches {
-                    barrier.wait();
+                if !unbounded {
+                    for _ in i..n_batches {
+                        barrier.wait();
+                    }
                 }
                 return;
             }
 
             // Compose a batch into the writers.
+            let batch_start = Instant::now();
             let mut writers =
                 EnumMap::from_fn(|table| Self::make_csv_writer(mem::take(&mut buffers[table])));
             let mut n = 0;
-            for NextEvent { event, .. } in &mut generator {
+            for NextEvent {
+                event,
+                wall_clock_timestamp,
+                ..
+            } in &mut generator
+            {
                 match event {
+                    Event::Person(person) if native[NexmarkTable::Person] => {
+                        native_records[NexmarkTable::Person].push(Box::new(person))
+                    }
                     Event::Person(person) => {
                         writers[NexmarkTable::Person].serialize(person).unwrap()
                     }
+                    Event::Auction(auction) if native[NexmarkTable::Auction] => {
+                        native_records[NexmarkTable::Auction].push(Box::new(auction))
+                    }
                     Event::Auction(auction) => {
                         writers[NexmarkTable::Auction].serialize(auction).unwrap()
                     }
+                    Event::Bid(bid) if native[NexmarkTable::Bid] => {
+                        native_records[NexmarkTable::Bid].push(Box::new(bid))
+                    }
                     Event::Bid(bid) => writers[NexmarkTable::Bid].serialize(bid).unwrap(),
                 }
                 n += 1;
-                if n >= options.batch_size_per_thread {
+                total_emitted += 1;
+                if let Some(delay) = pacer.delay_for(wall_clock_timestamp) {
+                    thread::park_timeout(delay);
+                    if matches!(self.status(), PipelineState::Terminated) {
+                        break;
+                    }
+                }
+                if n >= batch_size {
                     break;
                 }
             }
 
-            // Parse the batch into per-table InputBuffers.
+            if let Some(target) = options.target_batch_duration {
+                batch_size = Self::calibrate_batch_size(batch_size, batch_start.elapsed(), target);
+                if !unbounded {
+                    // `batch_size` just moved away from whatever `n_batches`
+                    // was last computed from; recompute it from the live
+                    // value so the barrier-wait count tracks how many
+                    // batches are actually left at this thread's per-event
+                    // share, rather than the stale estimate.
+                    let per_thread_events = options.events.div_ceil(options.threads as u64);
+                    let remaining = per_thread_events.saturating_sub(total_emitted);
+                    n_batches = (i + 1) + remaining.div_ceil(batch_size);
+                }
+            }
+
+            // Mark the point where the bounded backlog ends and the live feed
+            // begins, so downstream consumers/operators can observe the
+            // snapshot/subscribe transition.
+            if matches!(options.mode, NexmarkGenerationMode::SnapshotThenSubscribe)
+                && total_emitted >= options.events / options.threads as u64
+                && self
+                    .snapshot_complete
+                    .compare_exchange(false, true, Ordering::AcqRel, Ordering::Relaxed)
+                    .is_ok()
+            {
+                info!("Nexmark generator: snapshot backlog emitted, switching to live subscribe");
+            }
+
+            // Turn the batch into per-table InputBuffers: tables using the
+            // native fast path get their typed records fed directly to the
+            // parser, everything else goes through the CSV text it was
+            // serialized into above.
             let buffers = writers
                 .into_iter()
                 .map(|(table, writer)| {
-                    let data = writer.into_inner().unwrap().into_inner();
                     let parser = &mut parsers[table];
-                    let (buffer, _errors) = parser.parse(data.as_slice());
-                    buffer
+                    if native[table] {
+                        let records = mem::take(&mut native_records[table]);
+                        let (buffer, _errors) = parser.record_buffer(&records);
+                        buffer
+                    } else {
+                        let data = writer.into_inner().unwrap().into_inner();
+                        let (buffer, _errors) = parser.parse(data.as_slice());
+                        buffer
+                    }
                 })
                 .collect::<Vec<_>>();
             queue.lock().unwrap().extend(buffers.into_iter());
@@ -376,9 +594,10 @@ impl Inner {
                     .flatten()
                     .collect::<Vec<_>>();
                 let num_records = buffers.iter().map(|buffer| buffer.len()).sum();
-                self.buffers.lock().unwrap().push_back(buffers);
+                self.push_bounded(buffers, options.max_queued_batches);
                 consumer.buffered(num_records, 0);
             }
+            i += 1;
         }
     }
 }
@@ -32,7 +32,7 @@ use crate::probe::Probe;
 use crate::runner::interaction::RunnerInteraction;
 use actix_http::body::BoxBody;
 use actix_http::StatusCode;
-use actix_web::body::MessageBody;
+use actix_web::body::{BodySize, MessageBody};
 use actix_web::dev::{Service, ServiceResponse};
 use actix_web::http::Method;
 use actix_web::Scope;
@@ -47,7 +47,11 @@ use actix_web_static_files::ResourceFiles;
 use anyhow::{Error as AnyError, Result as AnyResult};
 use futures_util::FutureExt;
 use log::{error, log, trace, Level};
-use std::time::Duration;
+// Prefixed with `::` because this module also declares a local `metrics`
+// submodule, which would otherwise shadow the `metrics` crate.
+use ::metrics::{counter, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+use std::time::{Duration, Instant};
 use std::{env, net::TcpListener, sync::Arc};
 use termbg::{theme, Theme};
 use tokio::sync::Mutex;
@@ -107,6 +111,7 @@ The program version is used internally by the compiler to know when to recompile
         pipeline::get_pipeline_heap_profile,
         pipeline::pipeline_adhoc_sql,
         pipeline::checkpoint_pipeline,
+        get_pipeline_diagnostics,
 
         // HTTP input/output
         http_io::http_input,
@@ -145,6 +150,8 @@ The program version is used internally by the compiler to know when to recompile
         crate::api::pipeline::ListPipelinesQueryParameters,
         crate::api::pipeline::PatchPipeline,
         crate::api::pipeline::ExtendedPipelineDescrOptionalCode,
+        crate::api::DiagnosticsStreamMode,
+        crate::api::DiagnosticsQueryParameters,
 
         // Demo
         crate::demo::Demo,
@@ -245,6 +252,7 @@ fn public_scope() -> Scope {
         .service(config_api::get_config)
         .service(SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-doc/openapi.json", openapi))
         .service(healthz)
+        .service(get_manager_metrics)
         .service(ResourceFiles::new("/", generate()).resolve_not_found_to_root())
 }
 
@@ -268,6 +276,7 @@ fn api_scope() -> Scope {
         .service(pipeline::get_pipeline_heap_profile)
         .service(pipeline::pipeline_adhoc_sql)
         .service(pipeline::checkpoint_pipeline)
+        .service(get_pipeline_diagnostics)
         // API keys endpoints
         .service(api_key::list_api_keys)
         .service(api_key::get_api_key)
@@ -306,6 +315,51 @@ impl Modify for SecurityAddon {
     }
 }
 
+/// Shared conditional-GET support for endpoints whose body rarely changes
+/// between versions (e.g. circuit/heap profiles), so repeated polling by a
+/// UI client doesn't re-transfer the full artifact every time.
+///
+/// Checks the request's `If-None-Match`/`If-Modified-Since` headers against
+/// `etag`/`last_modified`. If the client's cached copy is current, returns a
+/// `304 Not Modified` response with no body (which `log_response` already
+/// special-cases); `log_response` already logs it at `Debug` rather than as
+/// an error. Otherwise returns `None`, and the caller should attach
+/// `conditional_headers` to its `200 OK` response.
+pub(crate) fn conditional_get(
+    req: &HttpRequest,
+    etag: &str,
+    last_modified: chrono::DateTime<chrono::Utc>,
+) -> Option<HttpResponse> {
+    let quoted_etag = format!("\"{etag}\"");
+    let not_modified = req
+        .headers()
+        .get("If-None-Match")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v == quoted_etag || v == "*")
+        .or_else(|| {
+            req.headers()
+                .get("If-Modified-Since")
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| chrono::DateTime::parse_from_rfc2822(v).ok())
+                .map(|since| last_modified <= since)
+        })
+        .unwrap_or(false);
+    not_modified.then(|| HttpResponse::NotModified().finish())
+}
+
+/// Attaches the `ETag`, `Last-Modified`, and `Cache-Control` headers that
+/// [`conditional_get`] checks requests against.
+pub(crate) fn conditional_headers(
+    builder: &mut actix_web::HttpResponseBuilder,
+    etag: &str,
+    last_modified: chrono::DateTime<chrono::Utc>,
+) {
+    builder
+        .insert_header(("ETag", format!("\"{etag}\"")))
+        .insert_header(("Last-Modified", last_modified.to_rfc2822()))
+        .insert_header(("Cache-Control", "private, must-revalidate"));
+}
+
 pub(crate) fn parse_string_param(
     req: &HttpRequest,
     param_name: &'static str,
@@ -333,6 +387,11 @@ pub(crate) struct ServerState {
     pub jwk_cache: Arc<Mutex<JwkCache>>,
     probe: Arc<Mutex<Probe>>,
     demos: Vec<Demo>,
+    /// Renders the api-server's own operational metrics (request counters,
+    /// latency and response-size histograms) in Prometheus text format.
+    /// Distinct from `metrics::get_metrics`, which aggregates metrics
+    /// scraped from running pipelines.
+    prometheus_handle: PrometheusHandle,
 }
 
 impl ServerState {
@@ -340,6 +399,8 @@ impl ServerState {
         let runner = RunnerInteraction::new(config.clone(), db.clone());
         let db_copy = db.clone();
         let demos = read_demos_from_directories(&config.demos_dir);
+        let prometheus_handle = PrometheusBuilder::new().install_recorder()?;
+        spawn_expired_api_key_sweep(db.clone());
         Ok(Self {
             db,
             runner,
@@ -347,10 +408,206 @@ impl ServerState {
             jwk_cache: Arc::new(Mutex::new(JwkCache::new())),
             probe: Probe::new(db_copy).await,
             demos,
+            prometheus_handle,
         })
     }
 }
 
+/// Interval at which [`spawn_expired_api_key_sweep`] checks for and deletes
+/// long-expired API keys, so time-bounded keys don't linger in the database
+/// forever once their `expires_at` has passed.
+const EXPIRED_API_KEY_SWEEP_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Spawns a background task that periodically deletes API keys whose
+/// `expires_at` is in the past. Runs for the lifetime of the process; errors
+/// are logged and the sweep simply retries on the next interval rather than
+/// taking down the api-server.
+///
+/// TODO: this sweep only reclaims storage for keys that have *already*
+/// expired. It does not enforce `not_before`/`expires_at` windows or
+/// pipeline scoping against requests made *before* a key expires -- that
+/// enforcement belongs in `auth::auth_validator`, rejecting with 401/403
+/// when the current time is outside the key's validity window or the
+/// requested pipeline is out of scope, and isn't implemented yet. Until
+/// then, issued keys are time-unbounded and pipeline-unscoped in practice
+/// regardless of what `not_before`/`expires_at`/pipeline list is stored on
+/// `ApiKeyDescr`.
+fn spawn_expired_api_key_sweep(db: Arc<Mutex<StoragePostgres>>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRED_API_KEY_SWEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = db.lock().await.delete_expired_api_keys().await {
+                error!("Failed to sweep expired API keys: {e}");
+            }
+        }
+    });
+}
+
+/// Controls how the stream returned by [`get_pipeline_diagnostics`] ends.
+/// Every mode streams the same items from the runner, and the connection
+/// stays open for as long as the runner's item stream does; the mode does
+/// not change which items are sent or when the stream closes.
+#[derive(Debug, Clone, Copy, Default, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DiagnosticsStreamMode {
+    /// Once the runner's item stream ends, sends a final empty batch (`[]`)
+    /// that signals completion.
+    #[default]
+    Snapshot,
+    /// Sends no terminating empty batch when the runner's item stream ends.
+    Subscribe,
+    /// Behaves exactly like `Subscribe`: no terminating empty batch.
+    SnapshotThenSubscribe,
+}
+
+/// Query parameters accepted by [`get_pipeline_diagnostics`].
+#[derive(Debug, Clone, Default, serde::Deserialize, utoipa::IntoParams)]
+pub(crate) struct DiagnosticsQueryParameters {
+    #[serde(default)]
+    mode: DiagnosticsStreamMode,
+    /// Restricts which stat fields or log sources are returned. Selectors
+    /// are evaluated server-side before serialization, so filtered-out data
+    /// is never materialized. When unset, everything is returned.
+    selector: Option<Vec<String>>,
+}
+
+/// Frames are flushed once the accumulated batch reaches this many bytes,
+/// so clients get bounded-size chunks instead of one huge body.
+const DIAGNOSTICS_BATCH_TARGET_BYTES: usize = 64 * 1024;
+
+/// Upper bound on how long a partial batch is held before being flushed
+/// anyway, so a quiet `Subscribe` stream still produces timely frames.
+const DIAGNOSTICS_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Buffers serialized JSON diagnostics items into batches, flushing a batch
+/// as soon as it reaches [`DIAGNOSTICS_BATCH_TARGET_BYTES`] or
+/// [`DIAGNOSTICS_FLUSH_INTERVAL`] elapses, whichever comes first. Modeled on
+/// Fuchsia archivist's `BatchIterator`: the consumer gets a sequence of
+/// bounded-size frames instead of having to buffer one arbitrarily large
+/// response.
+fn batch_diagnostics_items(
+    items: impl futures_util::Stream<Item = serde_json::Value> + Send + 'static,
+    mode: DiagnosticsStreamMode,
+) -> impl futures_util::Stream<Item = Result<actix_web::web::Bytes, ManagerError>> {
+    use futures_util::StreamExt;
+
+    async_stream::stream! {
+        tokio::pin!(items);
+        let mut batch: Vec<serde_json::Value> = Vec::new();
+        let mut batch_bytes = 0usize;
+        let mut deadline = tokio::time::Instant::now() + DIAGNOSTICS_FLUSH_INTERVAL;
+        loop {
+            tokio::select! {
+                item = items.next() => {
+                    match item {
+                        Some(item) => {
+                            batch_bytes += serde_json::to_vec(&item).map(|v| v.len()).unwrap_or(0);
+                            batch.push(item);
+                            if batch_bytes < DIAGNOSTICS_BATCH_TARGET_BYTES {
+                                continue;
+                            }
+                        }
+                        None => {
+                            if !batch.is_empty() {
+                                yield Ok(actix_web::web::Bytes::from(serde_json::to_vec(&batch).unwrap()));
+                            }
+                            if matches!(mode, DiagnosticsStreamMode::Snapshot) {
+                                yield Ok(actix_web::web::Bytes::from_static(b"[]"));
+                            }
+                            return;
+                        }
+                    }
+                }
+                _ = tokio::time::sleep_until(deadline) => {}
+            }
+            if !batch.is_empty() {
+                yield Ok(actix_web::web::Bytes::from(serde_json::to_vec(&batch).unwrap()));
+                batch = Vec::new();
+                batch_bytes = 0;
+            }
+            deadline = tokio::time::Instant::now() + DIAGNOSTICS_FLUSH_INTERVAL;
+        }
+    }
+}
+
+/// Streams a pipeline's logs and stats as a sequence of batched JSON
+/// frames, rather than the point-in-time snapshots `get_pipeline_logs` and
+/// `get_pipeline_stats` return. See [`DiagnosticsStreamMode`] for the
+/// available modes and [`batch_diagnostics_items`] for the framing.
+#[utoipa::path(
+    responses(
+        (status = OK, description = "Batched stream of pipeline logs and stats.", content_type = "application/json-seq"),
+        (status = NOT_FOUND, description = "Pipeline with that name does not exist.", body = feldera_types::error::ErrorResponse),
+    ),
+    params(
+        ("pipeline_name" = String, Path, description = "Unique pipeline name"),
+        DiagnosticsQueryParameters,
+    ),
+    tag = "Pipelines"
+)]
+#[get("/pipelines/{pipeline_name}/diagnostics")]
+async fn get_pipeline_diagnostics(
+    state: WebData<ServerState>,
+    req: HttpRequest,
+    query: web::Query<DiagnosticsQueryParameters>,
+) -> Result<HttpResponse, ManagerError> {
+    let pipeline_name = parse_string_param(&req, "pipeline_name")?;
+    let items = state
+        .runner
+        .stream_pipeline_diagnostics(&pipeline_name, query.selector.clone())
+        .await?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/json-seq")
+        .streaming(batch_diagnostics_items(items, query.mode)))
+}
+
+/// Returned by the deadline middleware installed in [`run`] when a request
+/// runs past its deadline, so the api-server sheds load (returning a
+/// structured `408 Request Timeout`) instead of piling up workers stuck on a
+/// slow or wedged pipeline (e.g. `pipeline_adhoc_sql`,
+/// `get_pipeline_circuit_profile`).
+#[derive(Debug, serde::Serialize)]
+struct RequestDeadlineExceeded {
+    deadline_ms: u128,
+}
+
+impl std::fmt::Display for RequestDeadlineExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "request exceeded its {}ms deadline", self.deadline_ms)
+    }
+}
+
+impl std::error::Error for RequestDeadlineExceeded {}
+
+impl feldera_types::error::DetailedError for RequestDeadlineExceeded {
+    fn error_code(&self) -> std::borrow::Cow<'static, str> {
+        std::borrow::Cow::Borrowed("RequestDeadlineExceeded")
+    }
+}
+
+impl actix_web::ResponseError for RequestDeadlineExceeded {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::REQUEST_TIMEOUT
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .json(feldera_types::error::ErrorResponse::from_error(self))
+    }
+}
+
+/// Returns the deadline to apply to a request: the `X-Request-Deadline-Ms`
+/// header if present and valid, else `default`.
+fn request_deadline(req: &actix_web::dev::ServiceRequest, default: Duration) -> Duration {
+    req.headers()
+        .get("X-Request-Deadline-Ms")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(default)
+}
+
 fn create_listener(api_config: &ApiServerConfig) -> AnyResult<TcpListener> {
     // Check that the port is available before turning into a daemon, so we can fail
     // early if the port is taken.
@@ -364,6 +621,59 @@ fn create_listener(api_config: &ApiServerConfig) -> AnyResult<TcpListener> {
     Ok(listener)
 }
 
+/// Reads the request's `Content-Length` header, defaulting to `0` for
+/// bodiless requests or malformed/missing headers.
+fn content_length(req: &HttpRequest) -> u64 {
+    req.headers()
+        .get("Content-Length")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(0)
+}
+
+/// Emits one structured "completed request" access-log line covering
+/// method, path, pipeline name (when the route has one), status, latency,
+/// and request/response body sizes. Verbosity is controlled by
+/// `ApiServerConfig::access_log_level` (off/warn/info, mirroring how
+/// `SQLX_QUERY_LEVEL` separates query-log verbosity from the rest of an app)
+/// and can be quieted further per route via
+/// `ApiServerConfig::is_access_log_quiet`, so polling endpoints like
+/// `/logs` or `/metrics` don't drown out the rest at the `info` level.
+fn log_access(
+    api_config: &ApiServerConfig,
+    req: &HttpRequest,
+    status: StatusCode,
+    request_size: u64,
+    response_size: u64,
+    elapsed: Duration,
+) {
+    let level = match api_config.access_log_level {
+        crate::config::AccessLogLevel::Off => return,
+        crate::config::AccessLogLevel::Warn
+            if !status.is_client_error() && !status.is_server_error() =>
+        {
+            return
+        }
+        crate::config::AccessLogLevel::Warn => Level::Warn,
+        crate::config::AccessLogLevel::Info => Level::Info,
+    };
+    if api_config.is_access_log_quiet(req.path()) {
+        return;
+    }
+    let pipeline_name = req.match_info().get("pipeline_name").unwrap_or("-");
+    log!(
+        level,
+        "access: method={} path={} pipeline={} status={} latency_ms={} req_bytes={} resp_bytes={}",
+        req.method(),
+        req.path(),
+        pipeline_name,
+        status.as_u16(),
+        elapsed.as_millis(),
+        request_size,
+        response_size,
+    );
+}
+
 /// Logs the responses of the web server.
 pub fn log_response(
     res: Result<ServiceResponse<BoxBody>, actix_web::Error>,
@@ -418,16 +728,59 @@ pub async fn run(db: Arc<Mutex<StoragePostgres>>, api_config: ApiServerConfig) -
             let server = HttpServer::new(move || {
                 let auth_middleware = HttpAuthentication::with_fn(crate::auth::auth_validator);
                 let client = WebData::new(awc::Client::new());
+                let request_timeout = api_config.request_timeout;
+                let access_log_config = api_config.clone();
                 App::new()
                     .app_data(state.clone())
                     .app_data(auth_configuration.clone())
                     .app_data(client)
-                    .wrap_fn(|req, srv| {
+                    .wrap_fn(move |req, srv| {
                         trace!("Request: {} {}", req.method(), req.path());
-                        srv.call(req).map(log_response)
+                        let start = Instant::now();
+                        let request_size = content_length(&req);
+                        let access_log_config = access_log_config.clone();
+                        srv.call(req).map(move |res| {
+                            if let Ok(response) = &res {
+                                let size = match response.response().body().size() {
+                                    BodySize::Sized(n) => n,
+                                    BodySize::None | BodySize::Stream => 0,
+                                };
+                                record_request_metrics(
+                                    response.request(),
+                                    response.status(),
+                                    size,
+                                    start.elapsed(),
+                                );
+                                log_access(
+                                    &access_log_config,
+                                    response.request(),
+                                    response.status(),
+                                    request_size,
+                                    size,
+                                    start.elapsed(),
+                                );
+                            }
+                            log_response(res)
+                        })
                     })
                     .wrap(api_config.cors())
-                    .service(api_scope().wrap(auth_middleware))
+                    .service(
+                        api_scope()
+                            .wrap(auth_middleware)
+                            .wrap_fn(move |req, srv| {
+                                let deadline = request_deadline(&req, request_timeout);
+                                let fut = srv.call(req);
+                                async move {
+                                    match tokio::time::timeout(deadline, fut).await {
+                                        Ok(res) => res,
+                                        Err(_) => Err(RequestDeadlineExceeded {
+                                            deadline_ms: deadline.as_millis(),
+                                        }
+                                        .into()),
+                                    }
+                                }
+                            }),
+                    )
                     .service(public_scope())
             });
             server.listen(listener)?.run()
@@ -435,18 +788,61 @@ pub async fn run(db: Arc<Mutex<StoragePostgres>>, api_config: ApiServerConfig) -
         None => {
             let server = HttpServer::new(move || {
                 let client = WebData::new(awc::Client::new());
+                let request_timeout = api_config.request_timeout;
+                let access_log_config = api_config.clone();
                 App::new()
                     .app_data(state.clone())
                     .app_data(client)
-                    .wrap_fn(|req, srv| {
+                    .wrap_fn(move |req, srv| {
                         trace!("Request: {} {}", req.method(), req.path());
-                        srv.call(req).map(log_response)
+                        let start = Instant::now();
+                        let request_size = content_length(&req);
+                        let access_log_config = access_log_config.clone();
+                        srv.call(req).map(move |res| {
+                            if let Ok(response) = &res {
+                                let size = match response.response().body().size() {
+                                    BodySize::Sized(n) => n,
+                                    BodySize::None | BodySize::Stream => 0,
+                                };
+                                record_request_metrics(
+                                    response.request(),
+                                    response.status(),
+                                    size,
+                                    start.elapsed(),
+                                );
+                                log_access(
+                                    &access_log_config,
+                                    response.request(),
+                                    response.status(),
+                                    request_size,
+                                    size,
+                                    start.elapsed(),
+                                );
+                            }
+                            log_response(res)
+                        })
                     })
                     .wrap(api_config.cors())
-                    .service(api_scope().wrap_fn(|req, srv| {
-                        let req = crate::auth::tag_with_default_tenant_id(req);
-                        srv.call(req)
-                    }))
+                    .service(
+                        api_scope()
+                            .wrap_fn(|req, srv| {
+                                let req = crate::auth::tag_with_default_tenant_id(req);
+                                srv.call(req)
+                            })
+                            .wrap_fn(move |req, srv| {
+                                let deadline = request_deadline(&req, request_timeout);
+                                let fut = srv.call(req);
+                                async move {
+                                    match tokio::time::timeout(deadline, fut).await {
+                                        Ok(res) => res,
+                                        Err(_) => Err(RequestDeadlineExceeded {
+                                            deadline_ms: deadline.as_millis(),
+                                        }
+                                        .into()),
+                                    }
+                                }
+                            }),
+                    )
                     .service(public_scope())
             });
             server.listen(listener)?.run()
@@ -485,3 +881,36 @@ async fn healthz(state: WebData<ServerState>) -> Result<HttpResponse, ManagerErr
     let probe = state.probe.lock().await;
     probe.status_as_http_response()
 }
+
+/// Renders the api-server's own operational metrics (as opposed to
+/// `metrics::get_metrics`, which aggregates metrics scraped from running
+/// pipelines) in Prometheus text exposition format.
+///
+/// This is an internal endpoint and as such is not exposed via OpenAPI.
+#[get("/metrics")]
+async fn get_manager_metrics(state: WebData<ServerState>) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(state.prometheus_handle.render())
+}
+
+/// Records a request counter, a latency histogram, and a response-body-size
+/// histogram per route template + HTTP method + status class, so the
+/// api-server's own `/metrics` endpoint gives operators Grafana-ready SLO
+/// data for the control plane.
+fn record_request_metrics(req: &HttpRequest, status: StatusCode, size: u64, elapsed: Duration) {
+    // `match_pattern()` collapses per-pipeline paths (e.g. `/v0/pipelines/foo`)
+    // down to their route template (`/v0/pipelines/{pipeline_name}`), so the
+    // label cardinality stays bounded regardless of how many pipelines exist.
+    let route = req.match_pattern().unwrap_or_else(|| req.path().to_string());
+    let method = req.method().to_string();
+    let status_class = format!("{}xx", status.as_u16() / 100);
+    let labels = [
+        ("route", route),
+        ("method", method),
+        ("status", status_class),
+    ];
+    counter!("feldera_api_requests_total", &labels).increment(1);
+    histogram!("feldera_api_request_duration_seconds", &labels).record(elapsed.as_secs_f64());
+    histogram!("feldera_api_response_size_bytes", &labels).record(size as f64);
+}
@@ -32,6 +32,7 @@
 //! TEST_DBSP_URL=http://localhost:8080 cargo test integration_test:: --package=pipeline-manager --features integration-test  -- --nocapture
 //! ```
 use std::{
+    io::Cursor,
     process::Command,
     time::{Duration, Instant},
 };
@@ -43,6 +44,7 @@ use aws_sdk_cognitoidentityprovider::config::Region;
 use colored::Colorize;
 use feldera_types::transport::http::Chunk;
 use futures_util::StreamExt;
+use rand::Rng;
 use serde_json::{json, Value};
 use serial_test::serial;
 use tempfile::TempDir;
@@ -182,6 +184,15 @@ async fn initialize_local_pipeline_manager_instance() -> TempDir {
     tmp_dir
 }
 
+/// A single `deployment_status`/`program_status` transition observed from
+/// the `/v0/pipelines/{name}/status_stream` server-sent-events endpoint.
+#[allow(dead_code)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StatusTransition {
+    deployment_status: Option<String>,
+    program_status: Option<String>,
+}
+
 struct TestConfig {
     dbsp_url: String,
     client: awc::Client,
@@ -192,13 +203,160 @@ struct TestConfig {
     resume_timeout: Duration,
     shutdown_timeout: Duration,
     failed_timeout: Duration,
+    /// Total time a single request is allowed to spend retrying before
+    /// giving up and returning/propagating the last outcome.
+    retry_budget: Duration,
+    /// Base delay for full-jitter exponential backoff between retries.
+    retry_base: Duration,
+    /// Upper bound on the computed backoff delay (before jitter).
+    retry_cap: Duration,
+    /// When set, every HTTP call emits a structured trace line (method,
+    /// endpoint, status, latency, request body size, correlation id).
+    /// Gated behind `TEST_HTTP_TRACE` so normal runs stay quiet.
+    http_trace: bool,
 }
 
+/// Monotonically increasing id assigned to every HTTP call issued through
+/// `TestConfig`, so a trace line (or a `wait_for_*` panic message) can be
+/// matched back to the exact request/response pair in `--nocapture` output.
+static NEXT_CORRELATION_ID: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
+
 impl TestConfig {
     fn endpoint_url<S: AsRef<str>>(&self, endpoint: S) -> String {
         format!("{}{}", self.dbsp_url, endpoint.as_ref())
     }
 
+    /// Full-jitter exponential backoff delay for retry attempt `n`: a
+    /// random duration in `[0, min(cap, base * 2^n)]`. Modeled on the
+    /// backoff/rate-limit handling in axiom-rs.
+    fn backoff_delay(&self, attempt: u32) -> Duration {
+        let max_ms = (self.retry_base.as_millis() << attempt.min(32))
+            .min(self.retry_cap.as_millis()) as u64;
+        Duration::from_millis(rand::thread_rng().gen_range(0..=max_ms))
+    }
+
+    /// Honors a `Retry-After` header (either integer seconds or an HTTP
+    /// date) in preference to the computed backoff delay.
+    fn retry_after_delay(response: &ClientResponse<Decoder<Payload>>) -> Option<Duration> {
+        let value = response.headers().get("Retry-After")?.to_str().ok()?;
+        if let Ok(secs) = value.parse::<u64>() {
+            return Some(Duration::from_secs(secs));
+        }
+        let when = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+        let now = chrono::Utc::now();
+        (when.with_timezone(&chrono::Utc) - now).to_std().ok()
+    }
+
+    /// Pre-emptively waits until `X-RateLimit-Reset` when
+    /// `X-RateLimit-Remaining` has hit zero, instead of issuing a request
+    /// we already know will be rate-limited.
+    fn rate_limit_delay(response: &ClientResponse<Decoder<Payload>>) -> Option<Duration> {
+        let remaining: u64 = response
+            .headers()
+            .get("X-RateLimit-Remaining")?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()?;
+        if remaining > 0 {
+            return None;
+        }
+        let reset: u64 = response
+            .headers()
+            .get("X-RateLimit-Reset")?
+            .to_str()
+            .ok()?
+            .parse()
+            .ok()?;
+        Some(Duration::from_secs(reset))
+    }
+
+    /// Centralized retry wrapper all verb helpers route through: retries on
+    /// connection errors and on 429/503 responses using full-jitter
+    /// exponential backoff, bounded by `retry_budget`. `make_request` must
+    /// build a fresh request (and, for verbs with a body, re-supply it) on
+    /// every attempt, since a sent `ClientRequest` can't be replayed.
+    async fn send_with_retry<F, Fut>(
+        &self,
+        mut make_request: F,
+    ) -> Result<ClientResponse<Decoder<Payload>>, SendRequestError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<ClientResponse<Decoder<Payload>>, SendRequestError>>,
+    {
+        let start = Instant::now();
+        let mut attempt = 0;
+        loop {
+            match make_request().await {
+                Ok(response)
+                    if matches!(
+                        response.status(),
+                        StatusCode::TOO_MANY_REQUESTS | StatusCode::SERVICE_UNAVAILABLE
+                    ) && start.elapsed() < self.retry_budget =>
+                {
+                    let delay = Self::retry_after_delay(&response)
+                        .or_else(|| Self::rate_limit_delay(&response))
+                        .unwrap_or_else(|| self.backoff_delay(attempt));
+                    println!("Request rate-limited/unavailable ({:?}), retrying in {delay:?}...", response.status());
+                    attempt += 1;
+                    sleep(delay).await;
+                }
+                Ok(response) => {
+                    if let Some(delay) = Self::rate_limit_delay(&response) {
+                        println!("Rate limit exhausted, waiting {delay:?} before the next request...");
+                        sleep(delay).await;
+                    }
+                    return Ok(response);
+                }
+                Err(e) if start.elapsed() < self.retry_budget => {
+                    let delay = self.backoff_delay(attempt);
+                    println!("Request failed ({e}), retrying in {delay:?}...");
+                    attempt += 1;
+                    sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Assigns a correlation id and, when `http_trace` is set, logs method,
+    /// endpoint, status/error, request body size and wall-clock latency for
+    /// the call wrapped by `send_with_retry`. Following pict-rs's "option to
+    /// log completed requests", this turns `--nocapture` guesswork into a
+    /// usable request timeline; the assigned id is also what `wait_for_*`
+    /// panic messages reference to point back at the request that produced
+    /// the unexpected state.
+    async fn send_traced<F, Fut>(
+        &self,
+        method: &str,
+        endpoint: &str,
+        body_size: usize,
+        make_request: F,
+    ) -> Result<ClientResponse<Decoder<Payload>>, SendRequestError>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<ClientResponse<Decoder<Payload>>, SendRequestError>>,
+    {
+        let correlation_id =
+            NEXT_CORRELATION_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let start = Instant::now();
+        let result = self.send_with_retry(make_request).await;
+        if self.http_trace {
+            match &result {
+                Ok(response) => println!(
+                    "[http #{correlation_id}] {method} {endpoint} -> {} ({body_size} req bytes, {:?})",
+                    response.status(),
+                    start.elapsed()
+                ),
+                Err(e) => println!(
+                    "[http #{correlation_id}] {method} {endpoint} -> error: {e} ({body_size} req bytes, {:?})",
+                    start.elapsed()
+                ),
+            }
+        }
+        result
+    }
+
     async fn get<S: AsRef<str>>(&self, endpoint: S) -> ClientResponse<Decoder<Payload>> {
         self.try_get(endpoint).await.unwrap()
     }
@@ -207,22 +365,33 @@ impl TestConfig {
         &self,
         endpoint: S,
     ) -> Result<ClientResponse<Decoder<Payload>>, SendRequestError> {
-        self.maybe_attach_bearer_token(self.client.get(self.endpoint_url(endpoint)))
-            .send()
-            .await
+        self.send_traced("GET", endpoint.as_ref(), 0, || {
+            self.maybe_attach_bearer_token(self.client.get(self.endpoint_url(&endpoint)))
+                .send()
+        })
+        .await
     }
 
+    // TODO: every call site here does `r.body().await.unwrap()` on a fully
+    // buffered response, which doesn't scale to large SELECTs. Once the query
+    // handler grows a `?fetch_size=N` chunked-cursor mode, add a helper
+    // alongside this one that drains the response incrementally instead of
+    // materializing it whole; the handler this would exercise lives outside
+    // this checkout.
     async fn adhoc_query<S: AsRef<str>>(
         &self,
         endpoint: S,
         query: S,
         format: S,
     ) -> ClientResponse<Decoder<Payload>> {
-        let r = self
-            .maybe_attach_bearer_token(self.client.get(self.endpoint_url(endpoint)))
-            .query(&[("sql", query.as_ref()), ("format", format.as_ref())])
-            .expect("query parameters are valid");
-        r.send().await.expect("request is successful")
+        self.send_traced("GET", endpoint.as_ref(), 0, || {
+            self.maybe_attach_bearer_token(self.client.get(self.endpoint_url(&endpoint)))
+                .query(&[("sql", query.as_ref()), ("format", format.as_ref())])
+                .expect("query parameters are valid")
+                .send()
+        })
+        .await
+        .expect("request is successful")
     }
 
     /// Return the result of an ad hoc query as a JSON array.
@@ -254,6 +423,74 @@ impl TestConfig {
         )
     }
 
+    /// Return the result of an ad hoc query decoded from the Arrow IPC
+    /// streaming format, as a JSON array shaped like [`Self::adhoc_query_json`]
+    /// so the two can be asserted equal in tests.
+    async fn adhoc_query_arrow(&self, endpoint: &str, query: &str) -> serde_json::Value {
+        let mut r = self.adhoc_query(endpoint, query, "arrow").await;
+        assert_eq!(r.status(), StatusCode::OK);
+
+        let body = r.body().await.unwrap();
+        let reader = arrow::ipc::reader::StreamReader::try_new(Cursor::new(body.as_ref()), None)
+            .expect("ad hoc query returned an invalid Arrow IPC stream");
+        let batches = reader
+            .collect::<Result<Vec<_>, _>>()
+            .expect("failed to decode Arrow IPC batch");
+
+        let rows = arrow_json::writer::record_batches_to_json_rows(
+            &batches.iter().collect::<Vec<_>>(),
+        )
+        .expect("failed to convert Arrow record batches to JSON rows");
+        serde_json::Value::Array(rows.into_iter().map(serde_json::Value::Object).collect())
+    }
+
+    /// Return the result of an ad hoc query decoded from CSV, with each cell
+    /// coerced to the JSON type it looks like (integer, float, boolean, or
+    /// string; an empty cell becomes null), as a JSON array shaped like
+    /// [`Self::adhoc_query_json`].
+    async fn adhoc_query_csv(&self, endpoint: &str, query: &str) -> serde_json::Value {
+        let mut r = self.adhoc_query(endpoint, query, "csv").await;
+        assert_eq!(r.status(), StatusCode::OK);
+
+        let body = r.body().await.unwrap();
+        let mut reader = csv::ReaderBuilder::new().from_reader(body.as_ref());
+        let headers = reader
+            .headers()
+            .expect("ad hoc query returned an invalid CSV header row")
+            .clone();
+
+        let rows = reader
+            .records()
+            .map(|record| {
+                let record = record.expect("ad hoc query returned an invalid CSV row");
+                serde_json::Value::Object(
+                    headers
+                        .iter()
+                        .zip(record.iter())
+                        .map(|(name, cell)| (name.to_string(), Self::coerce_csv_cell(cell)))
+                        .collect(),
+                )
+            })
+            .collect();
+        serde_json::Value::Array(rows)
+    }
+
+    /// Coerces a raw CSV cell into the JSON value it most plausibly
+    /// represents, so CSV results can be compared against JSON/Arrow ones.
+    fn coerce_csv_cell(cell: &str) -> serde_json::Value {
+        if cell.is_empty() {
+            serde_json::Value::Null
+        } else if let Ok(i) = cell.parse::<i64>() {
+            serde_json::Value::from(i)
+        } else if let Ok(f) = cell.parse::<f64>() {
+            serde_json::Value::from(f)
+        } else if let Ok(b) = cell.parse::<bool>() {
+            serde_json::Value::from(b)
+        } else {
+            serde_json::Value::from(cell)
+        }
+    }
+
     // TODO: currently unused
     // /// Performs GET request, asserts the status code is OK, and returns result.
     // async fn get_ok<S: AsRef<str>>(&self, endpoint: S) -> ClientResponse<Decoder<Payload>> {
@@ -272,10 +509,12 @@ impl TestConfig {
     }
 
     async fn post_no_body<S: AsRef<str>>(&self, endpoint: S) -> ClientResponse<Decoder<Payload>> {
-        self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(endpoint)))
-            .send()
-            .await
-            .unwrap()
+        self.send_traced("POST", endpoint.as_ref(), 0, || {
+            self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(&endpoint)))
+                .send()
+        })
+        .await
+        .unwrap()
     }
 
     // TODO: currently unused
@@ -295,10 +534,17 @@ impl TestConfig {
         endpoint: S,
         json: &Value,
     ) -> ClientResponse<Decoder<Payload>> {
-        self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(endpoint)))
-            .send_json(&json)
-            .await
-            .unwrap()
+        self.send_traced(
+            "POST",
+            endpoint.as_ref(),
+            json.to_string().len(),
+            || {
+                self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(&endpoint)))
+                    .send_json(&json)
+            },
+        )
+        .await
+        .unwrap()
     }
 
     async fn post_csv<S: AsRef<str>>(
@@ -306,10 +552,12 @@ impl TestConfig {
         endpoint: S,
         csv: String,
     ) -> ClientResponse<Decoder<Payload>> {
-        self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(endpoint)))
-            .send_body(csv)
-            .await
-            .unwrap()
+        self.send_traced("POST", endpoint.as_ref(), csv.len(), || {
+            self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(&endpoint)))
+                .send_body(csv.clone())
+        })
+        .await
+        .unwrap()
     }
 
     async fn post_json<S: AsRef<str>>(
@@ -317,10 +565,12 @@ impl TestConfig {
         endpoint: S,
         json: String,
     ) -> ClientResponse<Decoder<Payload>> {
-        self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(endpoint)))
-            .send_body(json)
-            .await
-            .unwrap()
+        self.send_traced("POST", endpoint.as_ref(), json.len(), || {
+            self.maybe_attach_bearer_token(self.client.post(self.endpoint_url(&endpoint)))
+                .send_body(json.clone())
+        })
+        .await
+        .unwrap()
     }
 
     async fn patch<S: AsRef<str>>(
@@ -328,10 +578,65 @@ impl TestConfig {
         endpoint: S,
         json: &Value,
     ) -> ClientResponse<Decoder<Payload>> {
-        self.maybe_attach_bearer_token(self.client.patch(self.endpoint_url(endpoint)))
-            .send_json(&json)
-            .await
-            .unwrap()
+        self.send_traced(
+            "PATCH",
+            endpoint.as_ref(),
+            json.to_string().len(),
+            || {
+                self.maybe_attach_bearer_token(self.client.patch(self.endpoint_url(&endpoint)))
+                    .send_json(&json)
+            },
+        )
+        .await
+        .unwrap()
+    }
+
+    /// Watches `/v0/pipelines/{name}/status_stream` and collects the
+    /// sequence of `deployment_status`/`program_status` transitions as they
+    /// happen, until `expected_transitions` have been observed or
+    /// `max_timeout` elapses. Unlike `wait_for_deployment_status`'s
+    /// fixed-interval polling, this lets tests assert on the exact sequence
+    /// of transitions a pipeline goes through, catching regressions where a
+    /// pipeline skips or repeats a lifecycle phase (e.g. passing through
+    /// `Paused` on its way elsewhere).
+    #[allow(dead_code)]
+    async fn watch_status(
+        &self,
+        pipeline_name: &str,
+        expected_transitions: usize,
+        max_timeout: Duration,
+    ) -> Vec<StatusTransition> {
+        let mut response = self
+            .get(format!("/v0/pipelines/{pipeline_name}/status_stream"))
+            .await;
+        assert!(response.status().is_success());
+
+        let start = Instant::now();
+        let mut transitions = Vec::new();
+        while transitions.len() < expected_transitions && start.elapsed() < max_timeout {
+            match timeout(Duration::from_millis(1_000), response.next()).await {
+                Ok(Some(Ok(bytes))) => {
+                    // Each SSE frame is a `data: <json>\n\n` line carrying the
+                    // new deployment_status/program_status values.
+                    for line in std::str::from_utf8(&bytes).unwrap_or("").lines() {
+                        let Some(data) = line.strip_prefix("data: ") else {
+                            continue;
+                        };
+                        let event: Value = serde_json::from_str(data).unwrap();
+                        transitions.push(StatusTransition {
+                            deployment_status: event["deployment_status"]
+                                .as_str()
+                                .map(String::from),
+                            program_status: event["program_status"].as_str().map(String::from),
+                        });
+                    }
+                }
+                Ok(Some(Err(e))) => panic!("status_stream error: {e}"),
+                Ok(None) => break,
+                Err(_) => (),
+            }
+        }
+        transitions
     }
 
     async fn delta_stream_request_json(
@@ -399,10 +704,58 @@ impl TestConfig {
     }
 
     async fn delete<S: AsRef<str>>(&self, endpoint: S) -> ClientResponse<Decoder<Payload>> {
-        self.maybe_attach_bearer_token(self.client.delete(self.endpoint_url(endpoint)))
-            .send()
+        self.send_traced("DELETE", endpoint.as_ref(), 0, || {
+            self.maybe_attach_bearer_token(self.client.delete(self.endpoint_url(&endpoint)))
+                .send()
+        })
+        .await
+        .unwrap()
+    }
+
+    /// Collects a self-contained evidence bundle for `pipeline_name` into
+    /// `artifacts/<pipeline_name>-<timestamp>/`: the full pipeline JSON,
+    /// pipeline logs, and runtime stats, so a failing `cargo test` run
+    /// leaves evidence on disk rather than only scrollback. Modeled on the
+    /// "reserve an artifacts dir per job and upload build outputs" pattern
+    /// from the build-o-tron CI driver.
+    async fn collect_artifacts(&self, pipeline_name: &str, reason: &str) -> std::path::PathBuf {
+        let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%S%.3f");
+        let dir =
+            std::path::PathBuf::from("artifacts").join(format!("{pipeline_name}-{timestamp}"));
+        std::fs::create_dir_all(&dir).expect("failed to create artifacts directory");
+        std::fs::write(dir.join("reason.txt"), reason).ok();
+
+        if let Ok(mut response) = self.try_get(format!("/v0/pipelines/{pipeline_name}")).await {
+            if let Ok(pipeline) = response.json::<Value>().await {
+                std::fs::write(
+                    dir.join("pipeline.json"),
+                    serde_json::to_string_pretty(&pipeline).unwrap_or_default(),
+                )
+                .ok();
+            }
+        }
+        if let Ok(mut response) = self
+            .try_get(format!("/v0/pipelines/{pipeline_name}/logs"))
             .await
-            .unwrap()
+        {
+            if let Ok(body) = response.body().await {
+                std::fs::write(dir.join("logs.txt"), body.as_ref()).ok();
+            }
+        }
+        if let Ok(mut response) = self
+            .try_get(format!("/v0/pipelines/{pipeline_name}/stats"))
+            .await
+        {
+            if let Ok(body) = response.body().await {
+                std::fs::write(dir.join("stats.json"), body.as_ref()).ok();
+            }
+        }
+
+        println!(
+            "Collected failure artifacts for {pipeline_name} ({reason}) into {}",
+            dir.display()
+        );
+        dir
     }
 
     /// Waits for pipeline program status to indicate it is fully compiled.
@@ -414,13 +767,15 @@ impl TestConfig {
         loop {
             // Retrieve pipeline
             let mut response = self.get(format!("/v0/pipelines/{pipeline_name}")).await;
+            let last_correlation_id =
+                NEXT_CORRELATION_ID.load(std::sync::atomic::Ordering::Relaxed) - 1;
             let pipeline = response.json::<Value>().await.unwrap();
 
             // Program version must match
             let found_program_version = pipeline["program_version"].as_i64().unwrap();
             if found_program_version != version {
                 panic!(
-                    "Program version {} does not match expected {}",
+                    "Program version {} does not match expected {} (request #{last_correlation_id})",
                     found_program_version, version
                 );
             }
@@ -435,8 +790,13 @@ impl TestConfig {
                 return;
             } else {
                 println!("Pipeline:\n{pipeline:#?}");
+                self.collect_artifacts(
+                    pipeline_name,
+                    &format!("compilation failed in status: {:?}", pipeline["program_status"]),
+                )
+                .await;
                 panic!(
-                    "Compilation failed in status: {:?}",
+                    "Compilation failed in status: {:?} (request #{last_correlation_id})",
                     pipeline["program_status"]
                 );
             }
@@ -455,7 +815,9 @@ impl TestConfig {
             // Timeout
             if start.elapsed() > timeout {
                 println!("Pipeline:\n{pipeline:#?}");
-                panic!("Compilation timeout ({timeout:?})");
+                self.collect_artifacts(pipeline_name, &format!("compilation timeout ({timeout:?})"))
+                    .await;
+                panic!("Compilation timeout ({timeout:?}) (last request #{last_correlation_id})");
             }
 
             tokio::time::sleep(Duration::from_secs(1)).await;
@@ -476,6 +838,8 @@ impl TestConfig {
         loop {
             // Retrieve pipeline
             let mut response = self.get(format!("/v0/pipelines/{pipeline_name}")).await;
+            let last_correlation_id =
+                NEXT_CORRELATION_ID.load(std::sync::atomic::Ordering::Relaxed) - 1;
             let pipeline = response.json::<Value>().await.unwrap();
 
             // Reached the status
@@ -499,7 +863,14 @@ impl TestConfig {
             // Timeout
             if start.elapsed() >= timeout {
                 println!("Pipeline:\n{pipeline:#?}");
-                panic!("Timeout ({timeout:?}) waiting for pipeline status {status:?}");
+                self.collect_artifacts(
+                    pipeline_name,
+                    &format!("timeout ({timeout:?}) waiting for pipeline status {status:?}"),
+                )
+                .await;
+                panic!(
+                    "Timeout ({timeout:?}) waiting for pipeline status {status:?} (last request #{last_correlation_id})"
+                );
             }
 
             sleep(Duration::from_millis(300)).await;
@@ -536,6 +907,20 @@ impl TestConfig {
             .clone();
         println!("Found {} pipeline(s) to clean up", pipelines.len());
 
+        // Collect artifacts for any pipeline left in an error state from a
+        // previous failing test, before it gets shut down and deleted.
+        for pipeline in &pipelines {
+            let pipeline_name = pipeline["name"].as_str().unwrap();
+            if pipeline["program_status"] == json!(ProgramStatus::SqlError)
+                || pipeline["program_status"] == json!(ProgramStatus::RustError)
+                || pipeline["program_status"] == json!(ProgramStatus::SystemError)
+                || pipeline["deployment_status"] == json!(PipelineStatus::Failed)
+            {
+                self.collect_artifacts(pipeline_name, "pipeline left in an error state")
+                    .await;
+            }
+        }
+
         // Shutdown the pipelines
         for pipeline in &pipelines {
             let pipeline_name = pipeline["name"].as_str().unwrap();
@@ -573,6 +958,248 @@ impl TestConfig {
     }
 }
 
+/// Error returned by [`NdjsonChunkDecoder::feed`]/[`NdjsonChunkDecoder::finish`]
+/// when a chunk boundary splits a record in a way that can't be decoded:
+/// invalid UTF-8, or UTF-8 that isn't a valid JSON record. Malformed input
+/// is something a misbehaving or malicious server can trigger, so it must
+/// surface as a `Result` rather than panicking the caller.
+#[derive(Debug)]
+pub(crate) enum NdjsonDecodeError {
+    InvalidUtf8(std::str::Utf8Error),
+    InvalidJson(serde_json::Error),
+}
+
+impl std::fmt::Display for NdjsonDecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidUtf8(e) => write!(f, "NDJSON record is not valid UTF-8: {e}"),
+            Self::InvalidJson(e) => write!(f, "NDJSON record is not valid JSON: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for NdjsonDecodeError {}
+
+/// Incrementally decodes newline-delimited JSON records from a sequence of
+/// byte chunks, the way a client would drain a chunked-cursor
+/// `?fetch_size=N` response (following sqlx's streaming `fetch(...)` model)
+/// instead of buffering the whole body with `r.body().await.unwrap()` first.
+/// A record split across a chunk boundary is carried over to the next
+/// chunk rather than being dropped or double-counted.
+///
+/// This is a standalone helper plus unit tests only: the
+/// `?fetch_size=N`/chunked-cursor query handler it's modeled on isn't
+/// implemented anywhere in this checkout (see the TODO on
+/// [`TestConfig::adhoc_query`]), so nothing actually feeds this decoder
+/// real chunks from `/v0/pipelines/{name}/query` yet.
+#[derive(Default)]
+pub(crate) struct NdjsonChunkDecoder {
+    pending: Vec<u8>,
+}
+
+impl NdjsonChunkDecoder {
+    /// Feeds one chunk of bytes in, returning every complete JSON record
+    /// terminated by a newline found so far. Bytes after the last newline
+    /// are buffered and prepended to the next chunk.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Result<Vec<Value>, NdjsonDecodeError> {
+        self.pending.extend_from_slice(chunk);
+        let mut records = Vec::new();
+        while let Some(newline_at) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=newline_at).collect();
+            let line = &line[..line.len() - 1];
+            if !line.is_empty() {
+                let text = std::str::from_utf8(line).map_err(NdjsonDecodeError::InvalidUtf8)?;
+                records.push(serde_json::from_str(text).map_err(NdjsonDecodeError::InvalidJson)?);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Call once the stream is exhausted to flush a final record that
+    /// wasn't newline-terminated.
+    pub(crate) fn finish(self) -> Result<Option<Value>, NdjsonDecodeError> {
+        if self.pending.is_empty() {
+            Ok(None)
+        } else {
+            let text = std::str::from_utf8(&self.pending).map_err(NdjsonDecodeError::InvalidUtf8)?;
+            let record = serde_json::from_str(text).map_err(NdjsonDecodeError::InvalidJson)?;
+            Ok(Some(record))
+        }
+    }
+}
+
+#[cfg(test)]
+mod ndjson_chunk_decoder_tests {
+    use super::NdjsonChunkDecoder;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn decodes_one_record_per_chunk() {
+        let mut decoder = NdjsonChunkDecoder::default();
+        assert_eq!(decoder.feed(b"{\"id\":1}\n").unwrap(), vec![json!({"id": 1})]);
+        assert_eq!(decoder.feed(b"{\"id\":2}\n").unwrap(), vec![json!({"id": 2})]);
+        assert_eq!(decoder.finish().unwrap(), None);
+    }
+
+    #[test]
+    fn carries_a_record_split_across_chunks() {
+        let mut decoder = NdjsonChunkDecoder::default();
+        assert_eq!(decoder.feed(b"{\"id\":").unwrap(), Vec::<Value>::new());
+        assert_eq!(decoder.feed(b"1}\n").unwrap(), vec![json!({"id": 1})]);
+    }
+
+    #[test]
+    fn flushes_a_trailing_record_with_no_final_newline() {
+        let mut decoder = NdjsonChunkDecoder::default();
+        assert_eq!(decoder.feed(b"{\"id\":1}").unwrap(), Vec::<Value>::new());
+        assert_eq!(decoder.finish().unwrap(), Some(json!({"id": 1})));
+    }
+
+    #[test]
+    fn decodes_multiple_records_delivered_in_one_chunk() {
+        let mut decoder = NdjsonChunkDecoder::default();
+        assert_eq!(
+            decoder.feed(b"{\"id\":1}\n{\"id\":2}\n").unwrap(),
+            vec![json!({"id": 1}), json!({"id": 2})]
+        );
+    }
+
+    #[test]
+    fn feed_reports_invalid_utf8_instead_of_panicking() {
+        let mut decoder = NdjsonChunkDecoder::default();
+        assert!(decoder.feed(&[0xff, 0xfe, b'\n']).is_err());
+    }
+
+    #[test]
+    fn feed_reports_invalid_json_instead_of_panicking() {
+        let mut decoder = NdjsonChunkDecoder::default();
+        assert!(decoder.feed(b"not json\n").is_err());
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum ParamSubstitutionError {
+    MissingParam { placeholder: usize },
+    UnsupportedType { placeholder: usize },
+}
+
+/// Substitutes `$1`, `$2`, ... placeholders in `sql` with `params` (0-indexed
+/// in the slice, 1-indexed in the placeholder), the way `query(...).bind(x)`
+/// does in sqlx and arbitrary key/value parameter passing does in
+/// tokio-postgres: each value is typed, escaped, and rendered as a SQL
+/// literal before substitution, so callers stop interpolating literals into
+/// queries themselves.
+///
+/// This is the substitution helper and its tests only. There's no
+/// `{ "sql": "...", "params": [...] }` body handling on
+/// `/v0/pipelines/{name}/query` yet for this to plug into, so no request to
+/// that endpoint actually goes through `substitute_params` today.
+pub(crate) fn substitute_params(sql: &str, params: &[Value]) -> Result<String, ParamSubstitutionError> {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let mut digits = String::new();
+        while let Some((_, d)) = chars.peek() {
+            if d.is_ascii_digit() {
+                digits.push(*d);
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        if digits.is_empty() {
+            out.push('$');
+            continue;
+        }
+        let placeholder: usize = digits.parse().expect("only ASCII digits were collected");
+        let value = params
+            .get(placeholder.wrapping_sub(1))
+            .ok_or(ParamSubstitutionError::MissingParam { placeholder })?;
+        out.push_str(&render_sql_literal(value, placeholder)?);
+    }
+    Ok(out)
+}
+
+/// Renders one bound parameter as a SQL literal: strings are single-quoted
+/// with embedded quotes doubled (the standard SQL escaping rule), numbers
+/// and booleans render as-is, `null` becomes the `NULL` keyword, and a
+/// string that parses as RFC 3339 is rendered as a `TIMESTAMP` literal.
+fn render_sql_literal(value: &Value, placeholder: usize) -> Result<String, ParamSubstitutionError> {
+    match value {
+        Value::Null => Ok("NULL".to_string()),
+        Value::Bool(b) => Ok(b.to_string()),
+        Value::Number(n) => Ok(n.to_string()),
+        Value::String(s) => {
+            if chrono::DateTime::parse_from_rfc3339(s).is_ok() {
+                Ok(format!("TIMESTAMP '{}'", s.replace('\'', "''")))
+            } else {
+                Ok(format!("'{}'", s.replace('\'', "''")))
+            }
+        }
+        Value::Array(_) | Value::Object(_) => Err(ParamSubstitutionError::UnsupportedType { placeholder }),
+    }
+}
+
+#[cfg(test)]
+mod substitute_params_tests {
+    use super::{substitute_params, ParamSubstitutionError};
+    use serde_json::json;
+
+    #[test]
+    fn substitutes_each_placeholder_with_its_typed_literal() {
+        let sql = substitute_params(
+            "SELECT * FROM t WHERE id = $1 AND active = $2",
+            &[json!(42), json!(true)],
+        )
+        .unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE id = 42 AND active = true");
+    }
+
+    #[test]
+    fn escapes_single_quotes_in_string_params() {
+        let sql = substitute_params("SELECT * FROM t WHERE s = $1", &[json!("O'Brien")]).unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE s = 'O''Brien'");
+    }
+
+    #[test]
+    fn renders_null_as_the_null_keyword() {
+        let sql = substitute_params("UPDATE t SET s = $1", &[json!(null)]).unwrap();
+        assert_eq!(sql, "UPDATE t SET s = NULL");
+    }
+
+    #[test]
+    fn renders_an_rfc3339_string_as_a_timestamp_literal() {
+        let sql = substitute_params("SELECT * FROM t WHERE ts = $1", &[json!("2024-03-14T09:30:00Z")]).unwrap();
+        assert_eq!(sql, "SELECT * FROM t WHERE ts = TIMESTAMP '2024-03-14T09:30:00Z'");
+    }
+
+    #[test]
+    fn reports_a_missing_param_instead_of_substituting_nothing() {
+        assert_eq!(
+            substitute_params("SELECT * FROM t WHERE id = $1", &[]),
+            Err(ParamSubstitutionError::MissingParam { placeholder: 1 })
+        );
+    }
+
+    #[test]
+    fn rejects_array_and_object_params() {
+        assert_eq!(
+            substitute_params("SELECT $1", &[json!([1, 2])]),
+            Err(ParamSubstitutionError::UnsupportedType { placeholder: 1 })
+        );
+    }
+
+    #[test]
+    fn a_bare_dollar_sign_with_no_digits_passes_through_unchanged() {
+        let sql = substitute_params("SELECT '$' || $1", &[json!(1)]).unwrap();
+        assert_eq!(sql, "SELECT '$' || 1");
+    }
+}
+
 async fn bearer_token() -> Option<String> {
     let client_id = std::env::var("TEST_CLIENT_ID");
     match client_id {
@@ -667,6 +1294,27 @@ async fn setup() -> TestConfig {
             .parse::<u64>()
             .unwrap(),
     );
+    let retry_budget = Duration::from_secs(
+        std::env::var("TEST_RETRY_BUDGET_SECS")
+            .unwrap_or("60".to_string())
+            .parse::<u64>()
+            .unwrap(),
+    );
+    let retry_base = Duration::from_millis(
+        std::env::var("TEST_RETRY_BASE_MS")
+            .unwrap_or("200".to_string())
+            .parse::<u64>()
+            .unwrap(),
+    );
+    let retry_cap = Duration::from_secs(
+        std::env::var("TEST_RETRY_CAP_SECS")
+            .unwrap_or("10".to_string())
+            .parse::<u64>()
+            .unwrap(),
+    );
+    let http_trace = std::env::var("TEST_HTTP_TRACE")
+        .map(|v| v != "0")
+        .unwrap_or(false);
     let config = TestConfig {
         dbsp_url,
         client,
@@ -677,6 +1325,10 @@ async fn setup() -> TestConfig {
         resume_timeout,
         shutdown_timeout,
         failed_timeout,
+        retry_budget,
+        retry_base,
+        retry_cap,
+        http_trace,
     };
     config.cleanup().await;
     config
@@ -1046,6 +1698,267 @@ async fn pipeline_start_without_compiling() {
     assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
 }
 
+/// One table's worth of records in a batch ingress request body, as would be
+/// accepted by a `POST /v0/pipelines/{name}/ingress` that applies inserts and
+/// deletes across several tables within a single pipeline step. Parsing and
+/// shape validation live here so a future handler (outside this checkout)
+/// can lean on them instead of re-deriving the contract; the actual
+/// application of `data` to each table's input handle is the part that
+/// needs the running pipeline and so isn't exercised by the tests below.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BatchIngressItem {
+    pub table: String,
+    pub format: String,
+    pub update_format: String,
+    pub data: Vec<Value>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BatchIngressError {
+    NotAnArray,
+    ItemNotAnObject { index: usize },
+    MissingField { index: usize, field: &'static str },
+    WrongFieldType { index: usize, field: &'static str },
+    DuplicateTable { index: usize, table: String },
+}
+
+/// Parses and validates a batch-ingress request body of the form
+/// `[{ "table": "t1", "format": "json", "update_format": "insert_delete",
+/// "data": [...] }, ...]`, rejecting anything a handler would have to reject
+/// before it could safely start applying a step: missing/mistyped fields, or
+/// the same table named twice (which would make "one atomic step" ambiguous
+/// about ordering between the two entries).
+pub(crate) fn parse_batch_ingress_body(body: &Value) -> Result<Vec<BatchIngressItem>, BatchIngressError> {
+    let items = body.as_array().ok_or(BatchIngressError::NotAnArray)?;
+    let mut parsed = Vec::with_capacity(items.len());
+    let mut seen_tables = std::collections::HashSet::new();
+    for (index, item) in items.iter().enumerate() {
+        let obj = item
+            .as_object()
+            .ok_or(BatchIngressError::ItemNotAnObject { index })?;
+        let field_str = |field: &'static str| -> Result<String, BatchIngressError> {
+            obj.get(field)
+                .ok_or(BatchIngressError::MissingField { index, field })?
+                .as_str()
+                .map(str::to_string)
+                .ok_or(BatchIngressError::WrongFieldType { index, field })
+        };
+        let table = field_str("table")?;
+        let format = field_str("format")?;
+        let update_format = field_str("update_format")?;
+        let data = obj
+            .get("data")
+            .ok_or(BatchIngressError::MissingField { index, field: "data" })?
+            .as_array()
+            .ok_or(BatchIngressError::WrongFieldType { index, field: "data" })?
+            .clone();
+        if !seen_tables.insert(table.clone()) {
+            return Err(BatchIngressError::DuplicateTable { index, table });
+        }
+        parsed.push(BatchIngressItem {
+            table,
+            format,
+            update_format,
+            data,
+        });
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod batch_ingress_tests {
+    use super::{parse_batch_ingress_body, BatchIngressError, BatchIngressItem};
+    use serde_json::json;
+
+    #[test]
+    fn parses_one_item_per_table() {
+        let body = json!([
+            {"table": "t1", "format": "json", "update_format": "insert_delete", "data": [{"insert": {"id": 1}}]},
+            {"table": "t2", "format": "json", "update_format": "raw", "data": [{"id": 2}]},
+        ]);
+        assert_eq!(
+            parse_batch_ingress_body(&body),
+            Ok(vec![
+                BatchIngressItem {
+                    table: "t1".to_string(),
+                    format: "json".to_string(),
+                    update_format: "insert_delete".to_string(),
+                    data: vec![json!({"insert": {"id": 1}})],
+                },
+                BatchIngressItem {
+                    table: "t2".to_string(),
+                    format: "json".to_string(),
+                    update_format: "raw".to_string(),
+                    data: vec![json!({"id": 2})],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_a_non_array_body() {
+        assert_eq!(
+            parse_batch_ingress_body(&json!({"table": "t1"})),
+            Err(BatchIngressError::NotAnArray)
+        );
+    }
+
+    #[test]
+    fn rejects_a_missing_field() {
+        let body = json!([{"table": "t1", "format": "json", "data": []}]);
+        assert_eq!(
+            parse_batch_ingress_body(&body),
+            Err(BatchIngressError::MissingField {
+                index: 0,
+                field: "update_format"
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_the_same_table_named_twice() {
+        let body = json!([
+            {"table": "t1", "format": "json", "update_format": "raw", "data": []},
+            {"table": "t1", "format": "json", "update_format": "raw", "data": []},
+        ]);
+        assert_eq!(
+            parse_batch_ingress_body(&body),
+            Err(BatchIngressError::DuplicateTable {
+                index: 1,
+                table: "t1".to_string(),
+            })
+        );
+    }
+}
+
+/// One parse failure recorded by [`skip_invalid_records`], with the
+/// `event_number`/`field`/`invalid_text` fields the ingress handler already
+/// uses for its `ParseErrors` response (outside this checkout) so an
+/// `on_error=skip` mode can reuse the same structure. Unlike that response,
+/// which numbers events from 1 (see `json_ingress` below), `event_number`
+/// here is the 0-based index of the line in `lines`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RecordParseError {
+    pub event_number: usize,
+    pub field: Option<String>,
+    pub invalid_text: String,
+}
+
+/// Parses every line with `parse`, the way `on_error=skip` should: unlike
+/// array-mode ingress, which `json_ingress` below shows to be
+/// all-or-nothing, records that parse successfully are kept even when a later (or earlier)
+/// record in the same request fails, instead of the single bad record
+/// discarding the whole batch.
+pub(crate) fn skip_invalid_records<T>(
+    lines: &[&str],
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> (Vec<T>, Vec<RecordParseError>) {
+    let mut accepted = Vec::new();
+    let mut errors = Vec::new();
+    for (event_number, line) in lines.iter().enumerate() {
+        match parse(line) {
+            Ok(record) => accepted.push(record),
+            Err(invalid_text) => errors.push(RecordParseError {
+                event_number,
+                field: None,
+                invalid_text,
+            }),
+        }
+    }
+    (accepted, errors)
+}
+
+#[cfg(test)]
+mod skip_invalid_records_tests {
+    use super::{skip_invalid_records, RecordParseError};
+
+    #[test]
+    fn keeps_every_record_that_parses_despite_a_bad_one_between_them() {
+        let lines = ["1", "not-a-number", "3"];
+        let (accepted, errors) =
+            skip_invalid_records(&lines, |s| s.parse::<i64>().map_err(|e| e.to_string()));
+        assert_eq!(accepted, vec![1, 3]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].event_number, 1);
+        assert_eq!(errors[0].invalid_text, "not-a-number".to_string());
+    }
+
+    #[test]
+    fn reports_no_errors_when_everything_parses() {
+        let lines = ["1", "2", "3"];
+        let (accepted, errors): (Vec<i64>, Vec<RecordParseError>) =
+            skip_invalid_records(&lines, |s| s.parse::<i64>().map_err(|e| e.to_string()));
+        assert_eq!(accepted, vec![1, 2, 3]);
+        assert!(errors.is_empty());
+    }
+}
+
+/// Builds one Debezium-compatible change event for egress: the symmetric
+/// counterpart of `update_format=debezium` ingress. `op` is derived from the
+/// underlying insert/delete weight the same way ingress interprets it on the
+/// way in (positive weight is an insert/update producing `after`, negative
+/// is a delete producing `before`), and `offset` is the monotonically
+/// increasing position of this change within the pipeline step that
+/// produced it.
+///
+/// No egress endpoint in this checkout calls this once per change and
+/// frames the results as newline-delimited JSON -- `debezium_envelope`
+/// builds one envelope at a time and is exercised directly by the tests
+/// below, not by a running change stream.
+pub(crate) fn debezium_envelope(weight: i64, before: Option<Value>, after: Option<Value>, offset: u64) -> Value {
+    let op = if weight < 0 {
+        "d"
+    } else if before.is_some() {
+        "u"
+    } else {
+        "c"
+    };
+    json!({
+        "payload": {
+            "op": op,
+            "before": before,
+            "after": after,
+        },
+        "offset": offset,
+    })
+}
+
+#[cfg(test)]
+mod debezium_envelope_tests {
+    use super::debezium_envelope;
+    use serde_json::json;
+
+    #[test]
+    fn a_positive_weight_with_no_prior_row_is_a_create() {
+        assert_eq!(
+            debezium_envelope(1, None, Some(json!({"id": 1})), 0),
+            json!({"payload": {"op": "c", "before": null, "after": {"id": 1}}, "offset": 0})
+        );
+    }
+
+    #[test]
+    fn a_positive_weight_replacing_a_prior_row_is_an_update() {
+        assert_eq!(
+            debezium_envelope(1, Some(json!({"id": 1, "v": 1})), Some(json!({"id": 1, "v": 2})), 1),
+            json!({"payload": {"op": "u", "before": {"id": 1, "v": 1}, "after": {"id": 1, "v": 2}}, "offset": 1})
+        );
+    }
+
+    #[test]
+    fn a_negative_weight_is_a_delete() {
+        assert_eq!(
+            debezium_envelope(-1, Some(json!({"id": 1})), None, 2),
+            json!({"payload": {"op": "d", "before": {"id": 1}, "after": null}, "offset": 2})
+        );
+    }
+
+    #[test]
+    fn offsets_are_carried_through_unchanged() {
+        let event = debezium_envelope(1, None, Some(json!({"id": 1})), 42);
+        assert_eq!(event["offset"], json!(42));
+    }
+}
+
 #[actix_web::test]
 #[serial]
 async fn json_ingress() {
@@ -1290,6 +2203,127 @@ async fn map_column() {
         .await;
 }
 
+/// One per-record failure from [`parse_configured_datetime`], reporting
+/// enough for a caller to point at the offending value without the whole
+/// ingress batch being rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct DatetimeParseError {
+    pub row: usize,
+    pub column: String,
+    pub value: String,
+}
+
+/// Parses `value` for `column` in row `row` using the first pattern in
+/// `patterns` (tried in order, `strptime`-style via `chrono`) that matches,
+/// treating a pattern without its own UTC offset as being in `input_tz` and
+/// converting to `target_tz`. Falls back to RFC 3339 if no configured
+/// pattern matches. No other layout is tried: the offset-less forms the
+/// default JSON ingress accepts (e.g. `2021-05-20 12:12:33`, see
+/// `parse_datetime` below) fail here unless a configured pattern covers
+/// them.
+///
+/// No connector config or ingress request in this checkout actually
+/// supplies `patterns`/`input_tz`/`target_tz`; this function and its
+/// formats are exercised only by the tests below.
+pub(crate) fn parse_configured_datetime(
+    row: usize,
+    column: &str,
+    value: &str,
+    patterns: &[&str],
+    input_tz: chrono::FixedOffset,
+    target_tz: chrono::FixedOffset,
+) -> Result<chrono::DateTime<chrono::FixedOffset>, DatetimeParseError> {
+    use chrono::TimeZone;
+    for pattern in patterns {
+        if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(value, pattern) {
+            if let Some(with_tz) = input_tz.from_local_datetime(&naive).single() {
+                return Ok(with_tz.with_timezone(&target_tz));
+            }
+        }
+    }
+    if let Ok(parsed) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Ok(parsed.with_timezone(&target_tz));
+    }
+    Err(DatetimeParseError {
+        row,
+        column: column.to_string(),
+        value: value.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod parse_configured_datetime_tests {
+    use super::{parse_configured_datetime, DatetimeParseError};
+    use chrono::TimeZone;
+
+    #[test]
+    fn parses_with_the_first_matching_configured_pattern() {
+        let utc = chrono::FixedOffset::east_opt(0).unwrap();
+        let result = parse_configured_datetime(
+            0,
+            "ts",
+            "03/14/2024 09:30",
+            &["%m/%d/%Y %H:%M"],
+            utc,
+            utc,
+        );
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap().format("%Y-%m-%d").to_string(), "2024-03-14");
+    }
+
+    #[test]
+    fn converts_between_input_and_target_timezones() {
+        let eastern_standard_time = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
+        let utc = chrono::FixedOffset::east_opt(0).unwrap();
+        let result = parse_configured_datetime(
+            0,
+            "ts",
+            "2024-03-14 09:30",
+            &["%Y-%m-%d %H:%M"],
+            eastern_standard_time,
+            utc,
+        )
+        .unwrap();
+        // 09:30 at UTC-5 is 14:30 UTC.
+        assert_eq!(result.format("%H:%M").to_string(), "14:30");
+    }
+
+    #[test]
+    fn falls_back_to_rfc3339_when_no_pattern_matches() {
+        let utc = chrono::FixedOffset::east_opt(0).unwrap();
+        let result = parse_configured_datetime(
+            0,
+            "ts",
+            "2024-03-14T09:30:00Z",
+            &["%m/%d/%Y %H:%M"],
+            utc,
+            utc,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn reports_a_structured_error_on_an_unparseable_value() {
+        let utc = chrono::FixedOffset::east_opt(0).unwrap();
+        let result = parse_configured_datetime(
+            3,
+            "ts",
+            "not-a-date",
+            &["%m/%d/%Y %H:%M"],
+            utc,
+            utc,
+        );
+        assert_eq!(
+            result,
+            Err(DatetimeParseError {
+                row: 3,
+                column: "ts".to_string(),
+                value: "not-a-date".to_string(),
+            })
+        );
+    }
+}
+
 #[actix_web::test]
 #[serial]
 async fn parse_datetime() {
@@ -1393,6 +2427,117 @@ async fn quoted_columns() {
         .await;
 }
 
+/// Per-primary-key version guard for the optimistic-concurrency ingress
+/// mode: each successful write to a key advances its counter by one, and a
+/// write is rejected as stale if the token it echoes back doesn't match the
+/// counter the guard currently holds for that key. Borrows the causality
+/// token model from Garage's K2V item API, scoped down to a single counter
+/// per key (K2V's vector-clock-per-node isn't needed here since every write
+/// to a `Storage` table already goes through one serializing point: the
+/// pipeline step).
+///
+/// No ingress handler in this checkout constructs or consults one of these
+/// yet -- `/v0/pipelines/{name}/ingress/{table}` still applies
+/// `insert`/`update`/`delete` commands without checking a per-key counter
+/// against anything. What's below is the guard's bookkeeping, proven out by
+/// the tests rather than by a write that actually gets rejected as stale
+/// over HTTP.
+#[derive(Debug, Default)]
+pub(crate) struct CausalityGuard {
+    // Keyed by the key's canonical JSON text rather than `Value` directly,
+    // since `serde_json::Value` doesn't implement `Hash`.
+    versions: std::collections::HashMap<String, u64>,
+}
+
+/// Opaque per-key causality token, as returned to ingress clients. Only
+/// equality/ordering on the underlying counter matters; the JSON shape is
+/// intentionally not part of the guard's API surface so the eventual HTTP
+/// encoding (e.g. base64 of a small struct) can change without touching
+/// this logic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct CausalityToken(u64);
+
+/// Returned when a write echoes a token that doesn't match the guard's
+/// current version for that key.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct StaleWrite {
+    pub presented: CausalityToken,
+    pub current: CausalityToken,
+}
+
+impl CausalityGuard {
+    /// Applies a write to `key`. `expected` is the token the client echoed
+    /// back from a previous read/write of this key, or `None` for a write
+    /// that doesn't care about ordering (today's last-writer-wins
+    /// behavior). On success, returns the new token for this key; the first
+    /// write to a key that's never been seen succeeds when it presents no
+    /// token.
+    pub(crate) fn apply(
+        &mut self,
+        key: Value,
+        expected: Option<CausalityToken>,
+    ) -> Result<CausalityToken, StaleWrite> {
+        let key = key.to_string();
+        let current = self.versions.get(&key).copied().unwrap_or(0);
+        if let Some(CausalityToken(expected)) = expected {
+            if expected != current {
+                return Err(StaleWrite {
+                    presented: CausalityToken(expected),
+                    current: CausalityToken(current),
+                });
+            }
+        }
+        let next = current + 1;
+        self.versions.insert(key, next);
+        Ok(CausalityToken(next))
+    }
+}
+
+#[cfg(test)]
+mod causality_guard_tests {
+    use super::{CausalityGuard, CausalityToken, StaleWrite};
+    use serde_json::json;
+
+    #[test]
+    fn first_write_to_a_key_without_a_token_succeeds() {
+        let mut guard = CausalityGuard::default();
+        assert_eq!(guard.apply(json!(1), None), Ok(CausalityToken(1)));
+    }
+
+    #[test]
+    fn sequential_writes_echoing_the_latest_token_advance_the_counter() {
+        let mut guard = CausalityGuard::default();
+        let t1 = guard.apply(json!(1), None).unwrap();
+        let t2 = guard.apply(json!(1), Some(t1)).unwrap();
+        assert!(t2 > t1);
+        assert_eq!(guard.apply(json!(1), Some(t2)), Ok(CausalityToken(3)));
+    }
+
+    #[test]
+    fn a_retried_stale_write_is_rejected_instead_of_clobbering_the_newer_one() {
+        let mut guard = CausalityGuard::default();
+        let t1 = guard.apply(json!(1), None).unwrap();
+        let t2 = guard.apply(json!(1), Some(t1)).unwrap();
+        // A retried/delayed request still echoing `t1` must not win over `t2`.
+        assert_eq!(
+            guard.apply(json!(1), Some(t1)),
+            Err(StaleWrite {
+                presented: t1,
+                current: t2,
+            })
+        );
+    }
+
+    #[test]
+    fn keys_are_independent() {
+        let mut guard = CausalityGuard::default();
+        let t1 = guard.apply(json!(1), None).unwrap();
+        // Writing a different key never conflicts with key 1's token.
+        assert_eq!(guard.apply(json!(2), None), Ok(CausalityToken(1)));
+        assert_eq!(guard.apply(json!(1), Some(t1)), Ok(CausalityToken(2)));
+    }
+}
+
 #[actix_web::test]
 #[serial]
 async fn primary_keys() {
@@ -1564,6 +2709,86 @@ create materialized view "v1" as select * from table1;"#,
         .await;
 }
 
+/// Encodes a batch of delta-stream change records (each an id plus an
+/// insert/delete weight) as a single Arrow IPC stream: one schema message
+/// (`id` plus an appended boolean `insert` column, following RisingWave's
+/// connector-node `StreamChunk` convention of carrying the weight inline
+/// with the row) followed by one record batch. This is the framing a
+/// `format=arrow` option on the delta-stream egress endpoint (outside this
+/// checkout) would emit per batch; building and round-tripping it doesn't
+/// need that endpoint.
+pub(crate) fn encode_delta_batch_as_arrow_ipc(ids: &[i64], inserts: &[bool]) -> Vec<u8> {
+    assert_eq!(ids.len(), inserts.len());
+    let schema = std::sync::Arc::new(arrow::datatypes::Schema::new(vec![
+        arrow::datatypes::Field::new("id", arrow::datatypes::DataType::Int64, false),
+        arrow::datatypes::Field::new("insert", arrow::datatypes::DataType::Boolean, false),
+    ]));
+    let batch = arrow::record_batch::RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            std::sync::Arc::new(arrow::array::Int64Array::from(ids.to_vec())),
+            std::sync::Arc::new(arrow::array::BooleanArray::from(inserts.to_vec())),
+        ],
+    )
+    .expect("id and insert columns have matching lengths");
+
+    let mut buf = Vec::new();
+    {
+        let mut writer = arrow::ipc::writer::StreamWriter::try_new(&mut buf, &schema)
+            .expect("schema is valid for an Arrow IPC stream");
+        writer.write(&batch).expect("failed to write Arrow record batch");
+        writer.finish().expect("failed to finish Arrow IPC stream");
+    }
+    buf
+}
+
+/// Decodes a stream produced by [`encode_delta_batch_as_arrow_ipc`] back into
+/// `(id, insert)` pairs, for asserting it round-trips and for comparing
+/// against the JSON decoding of the same change stream.
+pub(crate) fn decode_delta_batch_from_arrow_ipc(bytes: &[u8]) -> Vec<(i64, bool)> {
+    let reader =
+        arrow::ipc::reader::StreamReader::try_new(std::io::Cursor::new(bytes), None)
+            .expect("invalid Arrow IPC stream");
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.expect("failed to decode Arrow IPC batch");
+        let ids = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<arrow::array::Int64Array>()
+            .expect("id column is Int64");
+        let inserts = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<arrow::array::BooleanArray>()
+            .expect("insert column is Boolean");
+        for i in 0..batch.num_rows() {
+            rows.push((ids.value(i), inserts.value(i)));
+        }
+    }
+    rows
+}
+
+#[cfg(test)]
+mod delta_batch_arrow_tests {
+    use super::{decode_delta_batch_from_arrow_ipc, encode_delta_batch_as_arrow_ipc};
+
+    #[test]
+    fn round_trips_ids_and_weights() {
+        let ids = vec![1, 2, 3];
+        let inserts = vec![true, false, true];
+        let bytes = encode_delta_batch_as_arrow_ipc(&ids, &inserts);
+        let decoded = decode_delta_batch_from_arrow_ipc(&bytes);
+        assert_eq!(decoded, vec![(1, true), (2, false), (3, true)]);
+    }
+
+    #[test]
+    fn an_empty_batch_round_trips_to_no_rows() {
+        let bytes = encode_delta_batch_as_arrow_ipc(&[], &[]);
+        assert_eq!(decode_delta_batch_from_arrow_ipc(&bytes), Vec::new());
+    }
+}
+
 #[actix_web::test]
 #[serial]
 async fn duplicate_outputs() {
@@ -1805,6 +3030,291 @@ async fn pipeline_name_invalid() {
     assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 }
 
+/// One statement's position in a `/v0/pipelines/{name}/query/batch` request,
+/// carrying the tag a response entry should report alongside its result
+/// (text/json/parquet), mirroring Garage K2V's `InsertBatch`/`ReadBatch`
+/// per-item tagging.
+///
+/// There is no `/v0/pipelines/{name}/query/batch` route in this checkout,
+/// so nothing actually runs these statements against one circuit step --
+/// what's here is only the per-statement type and the splitting/validation
+/// around it, not the atomic executor the request asked for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct BatchQueryStatement {
+    pub index: usize,
+    pub sql: String,
+    pub format: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BatchQueryError {
+    NotAnArray,
+    ItemNotAnObject { index: usize },
+    MissingField { index: usize, field: &'static str },
+    WrongFieldType { index: usize, field: &'static str },
+    EmptyStatement { index: usize },
+}
+
+/// Parses and validates a `/v0/pipelines/{name}/query/batch` request body of
+/// the form `[{ "sql": "...", "format": "json" }, ...]`, assigning each
+/// statement the index its result should be tagged with in the response
+/// array so that INSERTs and SELECTs in the same batch stay in the order the
+/// caller submitted them.
+pub(crate) fn parse_query_batch(body: &Value) -> Result<Vec<BatchQueryStatement>, BatchQueryError> {
+    let items = body.as_array().ok_or(BatchQueryError::NotAnArray)?;
+    let mut parsed = Vec::with_capacity(items.len());
+    for (index, item) in items.iter().enumerate() {
+        let obj = item
+            .as_object()
+            .ok_or(BatchQueryError::ItemNotAnObject { index })?;
+        let sql = obj
+            .get("sql")
+            .ok_or(BatchQueryError::MissingField { index, field: "sql" })?
+            .as_str()
+            .ok_or(BatchQueryError::WrongFieldType { index, field: "sql" })?
+            .to_string();
+        if sql.trim().is_empty() {
+            return Err(BatchQueryError::EmptyStatement { index });
+        }
+        let format = obj
+            .get("format")
+            .ok_or(BatchQueryError::MissingField { index, field: "format" })?
+            .as_str()
+            .ok_or(BatchQueryError::WrongFieldType { index, field: "format" })?
+            .to_string();
+        parsed.push(BatchQueryStatement { index, sql, format });
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod query_batch_tests {
+    use super::{parse_query_batch, BatchQueryError, BatchQueryStatement};
+    use serde_json::json;
+
+    #[test]
+    fn parses_statements_preserving_submission_order() {
+        let body = json!([
+            {"sql": "INSERT INTO t1 VALUES (1)", "format": "json"},
+            {"sql": "SELECT COUNT(*) FROM t1", "format": "json"},
+        ]);
+        assert_eq!(
+            parse_query_batch(&body),
+            Ok(vec![
+                BatchQueryStatement {
+                    index: 0,
+                    sql: "INSERT INTO t1 VALUES (1)".to_string(),
+                    format: "json".to_string(),
+                },
+                BatchQueryStatement {
+                    index: 1,
+                    sql: "SELECT COUNT(*) FROM t1".to_string(),
+                    format: "json".to_string(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_a_blank_statement() {
+        let body = json!([{"sql": "   ", "format": "json"}]);
+        assert_eq!(
+            parse_query_batch(&body),
+            Err(BatchQueryError::EmptyStatement { index: 0 })
+        );
+    }
+
+    #[test]
+    fn rejects_a_non_array_body() {
+        assert_eq!(
+            parse_query_batch(&json!({"sql": "SELECT 1"})),
+            Err(BatchQueryError::NotAnArray)
+        );
+    }
+}
+
+/// `VECTOR(n)` distance scalar functions, following pgml's vector-search
+/// query builder. Each takes two equal-length `f32` vectors; the SQL type
+/// and its `ORDER BY <dist>(vec_col, <literal>) LIMIT k` executor integration
+/// live outside this checkout, but the distance math and exact top-k scan
+/// below are exercised directly.
+pub(crate) fn l2_distance(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(a.len(), b.len(), "VECTOR(n) operands must have the same length");
+    a.iter()
+        .zip(b)
+        .map(|(x, y)| (x - y).powi(2))
+        .sum::<f32>()
+        .sqrt()
+}
+
+pub(crate) fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(a.len(), b.len(), "VECTOR(n) operands must have the same length");
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        1.0
+    } else {
+        1.0 - dot / (norm_a * norm_b)
+    }
+}
+
+pub(crate) fn inner_product(a: &[f32], b: &[f32]) -> f32 {
+    debug_assert_eq!(a.len(), b.len(), "VECTOR(n) operands must have the same length");
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Evaluates `SELECT ... ORDER BY <dist>(vec_col, query) [ASC|DESC] LIMIT k`
+/// exactly: scans every row, scores it with `dist`, and returns the `k`
+/// nearest (ascending distance) or farthest (descending) rows in order. A
+/// real executor would index this to avoid the full scan; for the row
+/// counts these adhoc queries deal with, exact is simpler and cheaper to get
+/// right.
+pub(crate) fn top_k_by_distance<T: Clone>(
+    rows: &[(T, Vec<f32>)],
+    query: &[f32],
+    k: usize,
+    dist: impl Fn(&[f32], &[f32]) -> f32,
+    descending: bool,
+) -> Vec<(T, f32)> {
+    let mut scored: Vec<(T, f32)> = rows
+        .iter()
+        .map(|(row, vec)| (row.clone(), dist(vec, query)))
+        .collect();
+    scored.sort_by(|(_, a), (_, b)| {
+        if descending {
+            b.partial_cmp(a).unwrap()
+        } else {
+            a.partial_cmp(b).unwrap()
+        }
+    });
+    scored.truncate(k);
+    scored
+}
+
+#[cfg(test)]
+mod vector_distance_tests {
+    use super::{cosine_distance, inner_product, l2_distance, top_k_by_distance};
+
+    #[test]
+    fn l2_distance_of_identical_vectors_is_zero() {
+        assert_eq!(l2_distance(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]), 0.0);
+    }
+
+    #[test]
+    fn l2_distance_matches_the_pythagorean_case() {
+        assert_eq!(l2_distance(&[0.0, 0.0], &[3.0, 4.0]), 5.0);
+    }
+
+    #[test]
+    fn cosine_distance_of_parallel_vectors_is_zero() {
+        assert!(cosine_distance(&[1.0, 1.0], &[2.0, 2.0]).abs() < 1e-6);
+    }
+
+    #[test]
+    fn cosine_distance_of_orthogonal_vectors_is_one() {
+        assert!((cosine_distance(&[1.0, 0.0], &[0.0, 1.0]) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn inner_product_matches_the_dot_product() {
+        assert_eq!(inner_product(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), 32.0);
+    }
+
+    #[test]
+    fn top_k_returns_the_nearest_rows_in_ascending_distance_order() {
+        let rows = vec![
+            ("far", vec![10.0, 10.0]),
+            ("near", vec![0.1, 0.0]),
+            ("mid", vec![1.0, 0.0]),
+        ];
+        let result = top_k_by_distance(&rows, &[0.0, 0.0], 2, l2_distance, false);
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].0, "near");
+        assert_eq!(result[1].0, "mid");
+    }
+}
+
+/// Keyset-paginates pre-sorted query result rows the way
+/// `/v0/pipelines/{name}/query` should once it grows `limit`/`reverse`/
+/// `start` parameters, adopting K2V's ReadIndex pagination contract: `start`
+/// is the last key emitted by the previous page (or `None` for the first
+/// page), `reverse` walks the rows back-to-front, and the returned
+/// `more`/`next_start` tell the caller whether and how to fetch the next
+/// page. `rows` must already be sorted ascending by `key_of`.
+///
+/// `/v0/pipelines/{name}/query` doesn't accept `limit`/`reverse`/`start` in
+/// this checkout and still returns the full result set in one response, so
+/// this function is only reachable from its own tests below, not from a
+/// paginated query.
+pub(crate) fn paginate_query_rows<T: Clone, K: Ord + Clone>(
+    rows: &[T],
+    key_of: impl Fn(&T) -> K,
+    start: Option<K>,
+    limit: usize,
+    reverse: bool,
+) -> (Vec<T>, bool, Option<K>) {
+    let mut ordered: Vec<T> = if reverse {
+        let end = match &start {
+            Some(start) => rows.partition_point(|row| key_of(row) < *start),
+            None => rows.len(),
+        };
+        rows[..end].to_vec()
+    } else {
+        let from = match &start {
+            Some(start) => rows.partition_point(|row| key_of(row) <= *start),
+            None => 0,
+        };
+        rows[from..].to_vec()
+    };
+    if reverse {
+        ordered.reverse();
+    }
+    let more = ordered.len() > limit;
+    ordered.truncate(limit);
+    let next_start = if more { ordered.last().map(&key_of) } else { None };
+    (ordered, more, next_start)
+}
+
+#[cfg(test)]
+mod paginate_query_rows_tests {
+    use super::paginate_query_rows;
+
+    #[test]
+    fn walks_forward_pages_in_order() {
+        let rows: Vec<i32> = (0..10).collect();
+        let mut start = None;
+        let mut seen = Vec::new();
+        loop {
+            let (page, more, next) = paginate_query_rows(&rows, |r| *r, start, 3, false);
+            seen.extend(page);
+            if !more {
+                break;
+            }
+            start = next;
+        }
+        assert_eq!(seen, rows);
+    }
+
+    #[test]
+    fn walks_backward_from_a_start_key() {
+        let rows: Vec<i32> = (0..10).collect();
+        let (page, more, next) = paginate_query_rows(&rows, |r| *r, Some(7), 3, true);
+        assert_eq!(page, vec![6, 5, 4]);
+        assert!(more);
+        assert_eq!(next, Some(4));
+    }
+
+    #[test]
+    fn reports_no_more_on_the_last_page() {
+        let rows: Vec<i32> = (0..5).collect();
+        let (page, more, next) = paginate_query_rows(&rows, |r| *r, Some(2), 10, false);
+        assert_eq!(page, vec![3, 4]);
+        assert!(!more);
+        assert_eq!(next, None);
+    }
+}
+
 #[actix_web::test]
 #[serial]
 async fn pipeline_adhoc_query() {
@@ -2053,6 +3563,15 @@ CREATE TABLE "TaBle1"(id bigint not null) with ('materialized' = 'true');
 
 /// The pipeline should transition to Shutdown status when being shutdown after starting.
 /// This test will take at least 20 seconds due to various waiting times after starting.
+///
+/// `runner::error` now exposes the pluggable `Clock` trait and its
+/// `TokioClock` implementation (that module's own tests add a recording
+/// `ManualClock`), which this test would drive if the manager's
+/// state-transition code accepted one; `retry_with_backoff` in that module
+/// is already wired to it. The manager's actual `start_timeout`/`resume_timeout`/
+/// `shutdown_timeout` handling lives outside this checkout, so this test
+/// still has to poll real wall-clock time via `wait_for_deployment_status`
+/// until that code threads the same `Clock` through.
 #[actix_web::test]
 #[serial]
 async fn pipeline_shutdown_after_start() {
@@ -2127,6 +3646,93 @@ async fn test_get_metrics() {
     assert_eq!(StatusCode::OK, response.status());
 }
 
+/// One SSE event produced by [`log_lines_to_sse_events`]/consumed by
+/// [`resume_log_lines_from_offset`]: a log line tagged with the
+/// monotonically increasing byte/line offset a client can echo back via
+/// `Last-Event-ID`/`?from_offset=` to resume after a dropped connection,
+/// following actix-web's pipelined dispatcher model of keeping a live framed
+/// connection open and pushing events as they arrive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct LogEvent {
+    pub offset: u64,
+    pub name: &'static str,
+    pub data: String,
+}
+
+/// Frames a batch of stdout/stderr lines as SSE log events, keeping today's
+/// `LOG STREAM END`/`LOG STREAM UNAVAILABLE` sentinels but as named `end`/
+/// `unavailable` events instead of plain body text, so a client can tell
+/// them apart from an ordinary log line without string-matching the body.
+///
+/// The log-streaming endpoint in this checkout doesn't hold a connection
+/// open and push these as they're produced, and doesn't accept
+/// `Last-Event-ID`/`?from_offset=` to resume one -- only the framing and
+/// offset bookkeeping below are implemented, and only this file's tests
+/// call them.
+pub(crate) fn log_lines_to_sse_events(lines: &[&str], starting_offset: u64) -> Vec<LogEvent> {
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| LogEvent {
+            offset: starting_offset + i as u64,
+            name: if line.starts_with("LOG STREAM END") {
+                "end"
+            } else if line.starts_with("LOG STREAM UNAVAILABLE") {
+                "unavailable"
+            } else {
+                "log"
+            },
+            data: line.to_string(),
+        })
+        .collect()
+}
+
+/// Resumes a client that last saw `from_offset` (the value it would echo
+/// back via `Last-Event-ID`/`?from_offset=`) by dropping every event at or
+/// before that offset, so reconnecting after a drop replays nothing it
+/// already received and loses nothing it hasn't.
+pub(crate) fn resume_log_lines_from_offset(events: &[LogEvent], from_offset: u64) -> Vec<LogEvent> {
+    events
+        .iter()
+        .filter(|event| event.offset > from_offset)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod log_sse_resume_tests {
+    use super::{log_lines_to_sse_events, resume_log_lines_from_offset, LogEvent};
+
+    #[test]
+    fn frames_lines_with_monotonically_increasing_offsets() {
+        let events = log_lines_to_sse_events(&["line 1", "line 2"], 0);
+        assert_eq!(
+            events,
+            vec![
+                LogEvent { offset: 0, name: "log", data: "line 1".to_string() },
+                LogEvent { offset: 1, name: "log", data: "line 2".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn reconnecting_from_an_offset_replays_nothing_already_seen() {
+        let events = log_lines_to_sse_events(&["a", "b", "c", "d"], 0);
+        let resumed = resume_log_lines_from_offset(&events, 1);
+        assert_eq!(
+            resumed.iter().map(|e| e.data.as_str()).collect::<Vec<_>>(),
+            vec!["c", "d"]
+        );
+    }
+
+    #[test]
+    fn reconnecting_from_the_last_offset_replays_nothing_and_loses_nothing() {
+        let events = log_lines_to_sse_events(&["a", "b"], 0);
+        let last_offset = events.last().unwrap().offset;
+        assert!(resume_log_lines_from_offset(&events, last_offset).is_empty());
+    }
+}
+
 /// Tests that logs can be retrieved from the pipeline.
 /// TODO: test in the other deployment statuses whether logs can be retrieved
 #[actix_web::test]
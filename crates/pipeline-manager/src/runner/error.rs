@@ -2,9 +2,67 @@ use crate::db::types::pipeline::PipelineId;
 use actix_web::{
     body::BoxBody, http::StatusCode, HttpResponse, HttpResponseBuilder, ResponseError,
 };
+use async_trait::async_trait;
 use feldera_types::error::{DetailedError, ErrorResponse};
 use serde::Serialize;
-use std::{borrow::Cow, error::Error as StdError, fmt, fmt::Display, time::Duration};
+use std::{
+    borrow::Cow,
+    error::Error as StdError,
+    fmt,
+    fmt::Display,
+    future::Future,
+    time::{Duration, Instant},
+};
+
+/// Wraps a [`std::io::Error`] so it can be carried inside a [`RunnerError`]
+/// variant and serialized into an [`ErrorResponse`] without losing the
+/// underlying OS error code or `ErrorKind`.
+#[derive(Debug)]
+pub struct IOError(std::io::Error);
+
+impl IOError {
+    /// The raw OS error code (e.g. `111` for `ECONNREFUSED` on Linux), if any.
+    pub fn os_code(&self) -> Option<i32> {
+        self.0.raw_os_error()
+    }
+
+    /// The portable `ErrorKind` classification of the underlying error.
+    pub fn kind(&self) -> std::io::ErrorKind {
+        self.0.kind()
+    }
+}
+
+impl From<std::io::Error> for IOError {
+    fn from(error: std::io::Error) -> Self {
+        Self(error)
+    }
+}
+
+impl Display for IOError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl StdError for IOError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.0)
+    }
+}
+
+impl Serialize for IOError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("IOError", 3)?;
+        s.serialize_field("code", &self.os_code())?;
+        s.serialize_field("kind", &format!("{:?}", self.kind()))?;
+        s.serialize_field("message", &self.0.to_string())?;
+        s.end()
+    }
+}
 
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
@@ -65,15 +123,11 @@ pub enum RunnerError {
     // Runner
     PipelineStartupError {
         pipeline_id: PipelineId,
-        // TODO: This should be IOError, so we can serialize the error code
-        // similar to `DBSPError::IO`.
-        error: String,
+        error: IOError,
     },
     PipelineShutdownError {
         pipeline_id: PipelineId,
-        // TODO: This should be IOError, so we can serialize the error code
-        // similar to `DBSPError::IO`.
-        error: String,
+        error: IOError,
     },
     PortFileParseError {
         pipeline_id: PipelineId,
@@ -81,8 +135,33 @@ pub enum RunnerError {
     },
     BinaryFetchError {
         pipeline_id: PipelineId,
-        error: String,
+        error: IOError,
     },
+    // Retry
+    RetriesExhausted {
+        pipeline_id: PipelineId,
+        attempts: u32,
+        elapsed: Duration,
+        source: Box<RunnerError>,
+    },
+}
+
+impl RunnerError {
+    /// Returns whether this error represents a transient condition -- e.g.,
+    /// flaky infrastructure or a not-yet-ready dependency -- that may
+    /// succeed if the operation that produced it is retried, as opposed to
+    /// a terminal condition that retrying cannot fix.
+    pub fn is_retriable(&self) -> bool {
+        matches!(
+            self,
+            Self::PipelineProvisioningTimeout { .. }
+                | Self::PipelineInitializingTimeout { .. }
+                | Self::PipelineShutdownTimeout { .. }
+                | Self::PipelineEndpointSendError { .. }
+                | Self::PipelineEndpointResponseBodyError { .. }
+                | Self::BinaryFetchError { .. }
+        )
+    }
 }
 
 impl DetailedError for RunnerError {
@@ -113,8 +192,37 @@ impl DetailedError for RunnerError {
             Self::PipelineShutdownError { .. } => Cow::from("PipelineShutdownError"),
             Self::PortFileParseError { .. } => Cow::from("PortFileParseError"),
             Self::BinaryFetchError { .. } => Cow::from("BinaryFetchError"),
+            Self::RetriesExhausted { .. } => Cow::from("RetriesExhausted"),
         }
     }
+
+    fn error_type(&self) -> Cow<'static, str> {
+        match self {
+            Self::PipelineMissingDeploymentLocation { .. }
+            | Self::PipelineMissingProgramInfo { .. }
+            | Self::PipelineMissingProgramBinaryUrl { .. } => Cow::from("missing_resource"),
+            Self::PipelineNotRunningOrPaused { .. } => Cow::from("invalid_state"),
+            Self::PipelineEndpointSendError { .. }
+            | Self::PipelineEndpointResponseBodyError { .. }
+            | Self::PipelineEndpointResponseJsonParseError { .. }
+            | Self::PipelineEndpointInvalidResponse { .. } => Cow::from("upstream_communication"),
+            Self::PipelineProvisioningTimeout { .. }
+            | Self::PipelineInitializingTimeout { .. }
+            | Self::PipelineShutdownTimeout { .. } => Cow::from("timeout"),
+            Self::PipelineStartupError { .. }
+            | Self::PipelineShutdownError { .. }
+            | Self::PortFileParseError { .. }
+            | Self::BinaryFetchError { .. }
+            | Self::RetriesExhausted { .. } => Cow::from("internal"),
+        }
+    }
+
+    fn error_doc_url(&self) -> Cow<'static, str> {
+        Cow::from(format!(
+            "https://docs.feldera.com/errors#{}",
+            self.error_code().to_lowercase()
+        ))
+    }
 }
 
 impl Display for RunnerError {
@@ -261,6 +369,17 @@ impl Display for RunnerError {
                     "Failed to fetch binary executable for running pipeline {pipeline_id}: {error}"
                 )
             }
+            Self::RetriesExhausted {
+                pipeline_id,
+                attempts,
+                elapsed,
+                source,
+            } => {
+                write!(
+                    f,
+                    "Pipeline {pipeline_id} failed after {attempts} attempts over {elapsed:?}: {source}"
+                )
+            }
         }
     }
 }
@@ -271,7 +390,17 @@ impl From<RunnerError> for ErrorResponse {
     }
 }
 
-impl StdError for RunnerError {}
+impl StdError for RunnerError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::PipelineStartupError { error, .. } => Some(error),
+            Self::PipelineShutdownError { error, .. } => Some(error),
+            Self::BinaryFetchError { error, .. } => Some(error),
+            Self::RetriesExhausted { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
 
 impl ResponseError for RunnerError {
     fn status_code(&self) -> StatusCode {
@@ -286,17 +415,319 @@ impl ResponseError for RunnerError {
                 StatusCode::INTERNAL_SERVER_ERROR
             }
             Self::PipelineEndpointInvalidResponse { .. } => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::PipelineProvisioningTimeout { .. } => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::PipelineInitializingTimeout { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::PipelineProvisioningTimeout { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Self::PipelineInitializingTimeout { .. } => StatusCode::SERVICE_UNAVAILABLE,
             Self::PipelineStartupError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::PipelineShutdownError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::PipelineShutdownTimeout { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::PortFileParseError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::BinaryFetchError { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::RetriesExhausted { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        HttpResponseBuilder::new(self.status_code()).json(ErrorResponse::from_error(self))
+        let mut builder = HttpResponseBuilder::new(self.status_code());
+        if let Some(retry_after) = self.retry_after() {
+            builder.insert_header((
+                actix_web::http::header::RETRY_AFTER,
+                retry_after.as_secs().max(1).to_string(),
+            ));
+        }
+        builder.json(ErrorResponse::from_error(self))
+    }
+}
+
+impl RunnerError {
+    /// Suggested `Retry-After` delay for transient errors, or `None` if this
+    /// error is not one that a client should expect to resolve by retrying
+    /// shortly.
+    fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::PipelineProvisioningTimeout { timeout, .. }
+            | Self::PipelineInitializingTimeout { timeout, .. }
+            | Self::PipelineShutdownTimeout { timeout, .. } => Some(*timeout / 4),
+            Self::PipelineEndpointSendError { .. }
+            | Self::PipelineEndpointResponseBodyError { .. }
+            | Self::BinaryFetchError { .. } => Some(Duration::from_secs(1)),
+            _ => None,
+        }
+    }
+}
+
+/// Backoff parameters for [`retry_with_backoff`].
+///
+/// Configurable via the runner configuration so operators can tune how
+/// aggressively transient failures (e.g. a pipeline binary that isn't
+/// reachable yet) are retried before being surfaced as hard errors.
+#[derive(Debug, Clone, Copy)]
+pub struct RetryConfig {
+    /// Delay before the first retry. Doubles after each subsequent attempt.
+    pub base_delay: Duration,
+    /// Upper bound on the per-attempt delay.
+    pub max_delay: Duration,
+    /// Maximum number of attempts (including the first) before giving up.
+    pub max_attempts: u32,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_attempts: 5,
+        }
+    }
+}
+
+/// A pluggable source of "now" and "sleep" for code that waits on timeouts or
+/// backoff delays, so tests can advance time deterministically instead of
+/// waiting on the wall clock. Mirrors the extension point arti's
+/// `MockSleepProvider` gives its state machines: production code takes a
+/// `&dyn Clock` (as [`retry_with_backoff_and_clock`] does) and the manager's
+/// own state-transition/timeout logic (`start_timeout`, `resume_timeout`,
+/// `shutdown_timeout`; outside this checkout) should thread the same
+/// abstraction through once it adopts one, rather than calling
+/// `tokio::time::sleep` directly.
+#[async_trait]
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The default [`Clock`]: real wall-clock time via Tokio. Under
+/// `#[tokio::test(start_paused = true)]` this already behaves like a virtual
+/// clock (Tokio auto-advances paused time past pending timers), so most
+/// tests don't need [`ManualClock`] at all; it exists for tests that want to
+/// assert on the sequence of delays without giving Tokio's paused-time
+/// auto-advance a chance to run.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+#[async_trait]
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+/// Runs `f` until it succeeds, `config.max_attempts` is reached, or it
+/// returns a terminal (non-retriable) error.
+///
+/// On exhaustion of a retriable error, returns
+/// [`RunnerError::RetriesExhausted`] wrapping the last underlying error, so
+/// operators can see what was attempted.
+pub async fn retry_with_backoff<F, Fut>(
+    pipeline_id: PipelineId,
+    config: RetryConfig,
+    f: F,
+) -> Result<(), RunnerError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), RunnerError>>,
+{
+    retry_with_backoff_and_clock(pipeline_id, config, &TokioClock, f).await
+}
+
+/// Same as [`retry_with_backoff`], but takes an explicit [`Clock`] so callers
+/// (and tests) can substitute a deterministic one instead of sleeping for
+/// real.
+pub async fn retry_with_backoff_and_clock<F, Fut>(
+    pipeline_id: PipelineId,
+    config: RetryConfig,
+    clock: &dyn Clock,
+    mut f: F,
+) -> Result<(), RunnerError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), RunnerError>>,
+{
+    let start = clock.now();
+    let mut delay = config.base_delay;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match f().await {
+            Ok(()) => return Ok(()),
+            Err(e) if !e.is_retriable() => return Err(e),
+            Err(e) if attempt >= config.max_attempts => {
+                return Err(RunnerError::RetriesExhausted {
+                    pipeline_id,
+                    attempts: attempt,
+                    elapsed: clock.now().saturating_duration_since(start),
+                    source: Box::new(e),
+                })
+            }
+            Err(_) => {
+                clock.sleep(delay).await;
+                delay = (delay * 2).min(config.max_delay);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn test_pipeline_id() -> PipelineId {
+        PipelineId(uuid::Uuid::nil())
+    }
+
+    fn transient_error() -> RunnerError {
+        RunnerError::PipelineEndpointSendError {
+            pipeline_id: test_pipeline_id(),
+            pipeline_name: None,
+            url: "http://pipeline.invalid".to_string(),
+            error: "connection refused".to_string(),
+        }
+    }
+
+    fn terminal_error() -> RunnerError {
+        RunnerError::PipelineNotRunningOrPaused {
+            pipeline_id: test_pipeline_id(),
+            pipeline_name: "p1".to_string(),
+        }
+    }
+
+    /// A terminal (non-retriable) error is returned immediately, without
+    /// retrying or sleeping.
+    #[tokio::test]
+    async fn stops_immediately_on_terminal_error() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let attempts_clone = attempts.clone();
+        let result = retry_with_backoff(test_pipeline_id(), RetryConfig::default(), move || {
+            let attempts = attempts_clone.clone();
+            async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(terminal_error())
+            }
+        })
+        .await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+        assert!(matches!(
+            result,
+            Err(RunnerError::PipelineNotRunningOrPaused { .. })
+        ));
+    }
+
+    /// A transient error that keeps failing is retried up to
+    /// `max_attempts` times, with delays doubling between attempts up to
+    /// `max_delay`, and then surfaced as `RetriesExhausted`.
+    #[tokio::test(start_paused = true)]
+    async fn retries_transient_error_then_gives_up() {
+        let config = RetryConfig {
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(30),
+            max_attempts: 4,
+        };
+        let attempts = Arc::new(AtomicU32::new(0));
+        let attempts_clone = attempts.clone();
+        let result = retry_with_backoff(test_pipeline_id(), config, move || {
+            let attempts = attempts_clone.clone();
+            async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(transient_error())
+            }
+        })
+        .await;
+        assert_eq!(attempts.load(Ordering::SeqCst), config.max_attempts);
+        match result {
+            Err(RunnerError::RetriesExhausted {
+                attempts: reported, ..
+            }) => assert_eq!(reported, config.max_attempts),
+            other => panic!("expected RetriesExhausted, got {other:?}"),
+        }
+    }
+
+    /// A transient error that succeeds on a later attempt returns `Ok`
+    /// without exhausting `max_attempts`.
+    #[tokio::test(start_paused = true)]
+    async fn succeeds_after_transient_retries() {
+        let config = RetryConfig {
+            base_delay: Duration::from_millis(5),
+            max_delay: Duration::from_millis(20),
+            max_attempts: 5,
+        };
+        let attempts = Arc::new(AtomicU32::new(0));
+        let attempts_clone = attempts.clone();
+        let result = retry_with_backoff(test_pipeline_id(), config, move || {
+            let attempts = attempts_clone.clone();
+            async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(transient_error())
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    /// A [`Clock`] that never actually sleeps: it just records every
+    /// requested delay, so a test can assert on the exact backoff sequence
+    /// `retry_with_backoff` produces without Tokio's timer wheel (real or
+    /// paused) being involved at all.
+    #[derive(Default)]
+    struct ManualClock {
+        requested_sleeps: std::sync::Mutex<Vec<Duration>>,
+    }
+
+    #[async_trait]
+    impl Clock for ManualClock {
+        fn now(&self) -> Instant {
+            // `Instant` has no public "from zero" constructor; `now()` only
+            // needs to be monotonic for `elapsed()` bookkeeping, which the
+            // real clock already is, so there's no need for a fake one here.
+            Instant::now()
+        }
+
+        async fn sleep(&self, duration: Duration) {
+            self.requested_sleeps.lock().unwrap().push(duration);
+        }
+    }
+
+    /// Exercises [`retry_with_backoff_and_clock`] with [`ManualClock`] to
+    /// prove the exponential-backoff delay sequence is exactly what
+    /// `RetryConfig` promises, deterministically and without any real time
+    /// passing — the wiring the state-transition/timeout logic outside this
+    /// checkout should eventually reuse instead of calling
+    /// `tokio::time::sleep` directly.
+    #[tokio::test]
+    async fn backoff_delays_double_up_to_max_under_manual_clock() {
+        let config = RetryConfig {
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(35),
+            max_attempts: 5,
+        };
+        let clock = ManualClock::default();
+        let attempts = Arc::new(AtomicU32::new(0));
+        let attempts_clone = attempts.clone();
+        let result = retry_with_backoff_and_clock(test_pipeline_id(), config, &clock, move || {
+            let attempts = attempts_clone.clone();
+            async move {
+                attempts.fetch_add(1, Ordering::SeqCst);
+                Err(transient_error())
+            }
+        })
+        .await;
+        assert!(matches!(result, Err(RunnerError::RetriesExhausted { .. })));
+        assert_eq!(
+            *clock.requested_sleeps.lock().unwrap(),
+            vec![
+                Duration::from_millis(10),
+                Duration::from_millis(20),
+                Duration::from_millis(35),
+                Duration::from_millis(35),
+            ]
+        );
     }
 }
@@ -7,9 +7,145 @@ use crate::api::ProgramStatus;
 use crate::auth::TenantId;
 use crate::db::{ServiceDescr, ServiceId};
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use pipeline_types::config::{ConnectorConfig, RuntimeConfig, ServiceConfig};
+use serde_json::Value as Json;
+use std::time::Duration;
 use uuid::Uuid;
 
+/// Identifies a compiler worker claiming jobs from the compilation queue.
+/// Workers generate a fresh id on startup, so a restarted worker never
+/// appears to hold leases from its previous life.
+pub(crate) type WorkerId = Uuid;
+
+/// Identifies who performed an audited mutation: either the API key the
+/// request authenticated with, or a raw actor id for actions taken outside
+/// of API-key auth (e.g., tenant bootstrap via an OAuth provider identity).
+#[derive(Debug, Clone)]
+pub(crate) enum AuditActor {
+    ApiKey(ApiKeyDescr),
+    User(Uuid),
+}
+
+/// The kind of change an [`AuditEvent`] records. One variant per audited
+/// mutating `Storage` method.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum AuditAction {
+    ProgramCreated,
+    ProgramUpdated,
+    ProgramDeleted,
+    PipelineCreated,
+    PipelineUpdated,
+    PipelineDeleted,
+    ConnectorCreated,
+    ConnectorUpdated,
+    ConnectorDeleted,
+    ServiceCreated,
+    ServiceUpdated,
+    ServiceDeleted,
+    ApiKeyCreated,
+    ApiKeyDeleted,
+}
+
+/// A single row recorded by [`Storage::record_audit_event`] and returned by
+/// [`Storage::list_audit_events`].
+#[derive(Debug, Clone)]
+pub(crate) struct AuditEvent {
+    pub tenant_id: TenantId,
+    pub actor: AuditActor,
+    pub action: AuditAction,
+    pub object_id: Uuid,
+    /// JSON snapshot of the object before the mutation, `None` on creation.
+    pub before: Option<Json>,
+    /// JSON snapshot of the object after the mutation, `None` on deletion.
+    pub after: Option<Json>,
+    pub occurred_at: DateTime<Utc>,
+}
+
+/// Narrows [`Storage::list_audit_events`] to events matching all of the
+/// populated fields; `None` fields are unconstrained.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AuditEventFilter {
+    pub action: Option<AuditAction>,
+    pub object_id: Option<Uuid>,
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// A half-open `[start, end)` window used to scope [`Storage::tenant_usage`]
+/// rollups.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct UsageWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// Aggregated resource consumption for a tenant over a [`UsageWindow`],
+/// summed from the `record_pipeline_usage` samples taken in that window.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct TenantUsage {
+    pub cpu_seconds: f64,
+    pub bytes: u64,
+}
+
+/// How [`Storage::delete_program_with_mode`], [`Storage::delete_connector_with_mode`],
+/// and [`Storage::delete_service_with_mode`] should handle objects that
+/// still depend on the one being deleted. Either way, a program or service
+/// backing a currently-running pipeline always blocks deletion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DeleteMode {
+    /// Fail with [`DBError::DependenciesExist`] listing the blocking
+    /// pipelines/attached connectors instead of deleting anything.
+    Restrict,
+    /// Delete the object and everything that depends on it, transactionally,
+    /// and report what was removed.
+    Cascade,
+}
+
+/// What a cascading delete actually removed, returned by
+/// [`Storage::delete_program_with_mode`], [`Storage::delete_connector_with_mode`],
+/// and [`Storage::delete_service_with_mode`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct DeleteResult {
+    /// Pipelines deleted as a consequence of [`DeleteMode::Cascade`]; empty
+    /// under [`DeleteMode::Restrict`] or if nothing depended on the object.
+    pub cascaded_pipelines: Vec<PipelineId>,
+    /// Attached connectors detached as a consequence of
+    /// [`DeleteMode::Cascade`].
+    pub cascaded_connectors: Vec<ConnectorId>,
+    /// Total rows removed across the target object and all cascaded
+    /// dependents, e.g. for surfacing in an API response.
+    pub rows_affected: u64,
+}
+
+/// Extracts the `Uuid` that `id`'s `Display` impl renders as, for sorting
+/// and comparing the id newtypes ([`ProgramId`], [`PipelineId`], etc.) that
+/// the default `list_*_page` implementations below paginate by.
+fn id_as_uuid<T: std::fmt::Display>(id: &T) -> Uuid {
+    id.to_string()
+        .parse()
+        .expect("id newtypes display as their underlying Uuid")
+}
+
+/// Keyset-paginates `rows`, already sorted by `id_of`, returning the page
+/// starting just after `after` (or from the beginning if `None`) with at
+/// most `limit` rows, plus the id of the last row in the page to pass as
+/// `after` on the next call.
+fn paginate_by_id<T>(
+    mut rows: Vec<T>,
+    id_of: impl Fn(&T) -> Uuid,
+    after: Option<Uuid>,
+    limit: usize,
+) -> (Vec<T>, Option<Uuid>) {
+    rows.sort_by_key(&id_of);
+    let start = match after {
+        Some(after) => rows.partition_point(|row| id_of(row) <= after),
+        None => 0,
+    };
+    let page: Vec<T> = rows.into_iter().skip(start).take(limit).collect();
+    let next = page.last().map(&id_of);
+    (page, next)
+}
+
 /// The storage trait contains the methods to interact with the pipeline manager
 /// storage layer (e.g., PostgresDB) to implement the public API.
 ///
@@ -22,6 +158,35 @@ pub(crate) trait Storage {
         with_code: bool,
     ) -> Result<Vec<ProgramDescr>, DBError>;
 
+    /// Keyset-paginated variant of [`Self::list_programs`]: returns programs
+    /// with id greater than `after` (`None` to start from the beginning),
+    /// ordered by id, up to `limit` rows, plus the id of the last row
+    /// returned (`None` once the tenant's programs are exhausted) to pass as
+    /// `after` on the next call. Prefer this over `list_programs` for large
+    /// tenants — unlike `OFFSET`-based paging it stays `O(limit)` per page
+    /// and is stable under concurrent inserts.
+    ///
+    /// Default implementation: fetches the full list via
+    /// [`Self::list_programs`] and paginates it in memory, so it stays
+    /// `O(n)` per page rather than the `O(limit)` an index-backed `WHERE id
+    /// > $1 ORDER BY id LIMIT $2` query gives. Backends with such an index
+    /// should override this.
+    async fn list_programs_page(
+        &self,
+        tenant_id: TenantId,
+        with_code: bool,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> Result<(Vec<ProgramDescr>, Option<Uuid>), DBError> {
+        let programs = self.list_programs(tenant_id, with_code).await?;
+        Ok(paginate_by_id(
+            programs,
+            |p| id_as_uuid(&p.program_id),
+            after,
+            limit,
+        ))
+    }
+
     /// Update program schema.
     ///
     /// # Note
@@ -117,14 +282,31 @@ pub(crate) trait Storage {
         with_code: bool,
     ) -> Result<ProgramDescr, DBError>;
 
+    /// Delete program from the database, same as [`Self::delete_program_with_mode`]
+    /// under [`DeleteMode::Cascade`], discarding the [`DeleteResult`]. This
+    /// will delete all dependent pipelines. Kept for source compatibility
+    /// with callers that predate [`DeleteMode`]; those callers relied on
+    /// the original cascading behavior, so this default preserves it rather
+    /// than silently switching to [`DeleteMode::Restrict`].
+    async fn delete_program(&self, tenant_id: TenantId, program_id: ProgramId) -> Result<(), DBError> {
+        self.delete_program_with_mode(tenant_id, program_id, DeleteMode::Cascade)
+            .await?;
+        Ok(())
+    }
+
     /// Delete program from the database.
     ///
-    /// This will delete all program configs and pipelines.
-    async fn delete_program(
+    /// Under [`DeleteMode::Restrict`], fails with
+    /// [`DBError::DependenciesExist`] if any pipeline still references this
+    /// program. Under [`DeleteMode::Cascade`], those pipelines are deleted
+    /// too and reported in [`DeleteResult::cascaded_pipelines`]. Either way,
+    /// a currently-running pipeline always blocks the delete.
+    async fn delete_program_with_mode(
         &self,
         tenant_id: TenantId,
         program_id: ProgramId,
-    ) -> Result<(), DBError>;
+        mode: DeleteMode,
+    ) -> Result<DeleteResult, DBError>;
 
     /// Retrieves all programs in the DB. Intended to be used by
     /// reconciliation loops.
@@ -140,6 +322,181 @@ pub(crate) trait Storage {
     /// if there are no pending programs in the DB.
     async fn next_job(&self) -> Result<Option<(TenantId, ProgramId, Version)>, DBError>;
 
+    /// Waits for a program to become pending, then returns it.
+    ///
+    /// Backed by a Postgres `LISTEN program_queue_channel`/`NOTIFY` pair: a
+    /// trigger fires the notification whenever `set_program_status_guarded`/
+    /// `update_program` moves a program into [`ProgramStatus::Pending`], and
+    /// a background task forwards delivered notifications into a
+    /// `tokio::sync::Notify` that this method awaits. Because notifications
+    /// can be spurious or arrive for a job another worker already claimed,
+    /// this loops: wake on the `Notify`, run [`Self::next_job`], and only
+    /// return once it actually yields a row. Compiler workers should prefer
+    /// this over polling [`Self::next_job`] on a timer; `next_job` itself
+    /// remains the fallback for callers without a listener connection.
+    ///
+    /// Default implementation for backends without a `LISTEN`/`NOTIFY`
+    /// channel: polls [`Self::next_job`] on [`Self::WAIT_FOR_NEXT_JOB_POLL_INTERVAL`].
+    /// Backends with a listener connection should override this with the
+    /// event-driven behavior described above to avoid the polling delay.
+    async fn wait_for_next_job(&self) -> Result<(TenantId, ProgramId, Version), DBError> {
+        loop {
+            if let Some(job) = self.next_job().await? {
+                return Ok(job);
+            }
+            tokio::time::sleep(Self::WAIT_FOR_NEXT_JOB_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Poll interval used by the default [`Self::wait_for_next_job`]
+    /// implementation.
+    const WAIT_FOR_NEXT_JOB_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+    /// Atomically claims the next pending compilation job for `worker_id`.
+    ///
+    /// Backed by a `compilation_queue` table and a `SELECT ... FOR UPDATE SKIP
+    /// LOCKED` query, so multiple compiler workers can poll concurrently
+    /// without claiming the same job twice. A claimed job is held under a
+    /// lease that expires after `lease_duration` unless renewed via
+    /// [`Self::renew_compilation_job_lease`]; expired leases are made
+    /// available again by [`Self::reclaim_expired_compilation_leases`].
+    ///
+    /// Returns `None` if there are no unclaimed, pending jobs.
+    ///
+    /// Default implementation for backends without a dedicated lease table:
+    /// delegates to [`Self::next_job`] and does not actually track a lease,
+    /// so [`Self::renew_compilation_job_lease`] and
+    /// [`Self::reclaim_expired_compilation_leases`] are no-ops against it.
+    /// Backends that need crash-safe leasing (e.g. a Postgres
+    /// `compilation_queue` table with `SELECT ... FOR UPDATE SKIP LOCKED`)
+    /// should override all four lease methods together.
+    async fn claim_next_compilation_job(
+        &self,
+        _worker_id: WorkerId,
+        _lease_duration: Duration,
+    ) -> Result<Option<(TenantId, ProgramId, Version)>, DBError> {
+        self.next_job().await
+    }
+
+    /// Extends the lease on a job previously claimed by `worker_id`, so long
+    /// as the program is still at `expected_version`. Called periodically by
+    /// the worker as a heartbeat while compilation is in progress.
+    ///
+    /// Returns an error if the lease has expired or was reclaimed by another
+    /// worker, or if `expected_version` no longer matches (the program was
+    /// superseded by a newer edit).
+    ///
+    /// No-op by default, pairing with the [`Self::claim_next_compilation_job`]
+    /// default, which doesn't track a lease to renew.
+    async fn renew_compilation_job_lease(
+        &self,
+        _worker_id: WorkerId,
+        _program_id: ProgramId,
+        _expected_version: Version,
+        _lease_duration: Duration,
+    ) -> Result<(), DBError> {
+        Ok(())
+    }
+
+    /// Releases the lease held by `worker_id` on `program_id`, e.g., after
+    /// compilation succeeds or fails. A no-op if the worker no longer holds
+    /// the lease (it was already reclaimed).
+    ///
+    /// No-op by default, pairing with the [`Self::claim_next_compilation_job`]
+    /// default.
+    async fn release_compilation_job_lease(
+        &self,
+        _worker_id: WorkerId,
+        _program_id: ProgramId,
+    ) -> Result<(), DBError> {
+        Ok(())
+    }
+
+    /// Reclaims leases whose `lease_expires_at` has passed, making those
+    /// programs eligible to be claimed again. Intended to be called
+    /// periodically by a reaper task; returns the programs that were
+    /// reclaimed so the caller can log them.
+    ///
+    /// Always returns an empty list by default, since the
+    /// [`Self::claim_next_compilation_job`] default doesn't track leases to
+    /// expire.
+    async fn reclaim_expired_compilation_leases(
+        &self,
+    ) -> Result<Vec<(TenantId, ProgramId)>, DBError> {
+        Ok(Vec::new())
+    }
+
+    /// Requeues every job left in a claimed-but-unleased state, i.e., whose
+    /// worker crashed before the manager itself restarted (so the periodic
+    /// reaper never got to run). Intended to be called once on manager
+    /// startup, before workers start polling; returns the programs that were
+    /// requeued so the caller can log them.
+    ///
+    /// Always returns an empty list by default, since the
+    /// [`Self::claim_next_compilation_job`] default doesn't track claims to
+    /// find orphans among.
+    async fn requeue_orphaned_compilation_jobs(
+        &self,
+    ) -> Result<Vec<(TenantId, ProgramId)>, DBError> {
+        Ok(Vec::new())
+    }
+
+    /// Marks `program_id`'s queued or in-progress compilation job as
+    /// aborted, e.g., because the program was deleted while compiling. The
+    /// worker holding the lease, if any, is expected to check this flag on
+    /// its next heartbeat and bail out rather than persisting a result for a
+    /// program that no longer exists. A no-op if there is no active job for
+    /// `program_id`.
+    ///
+    /// No-op by default, pairing with the [`Self::claim_next_compilation_job`]
+    /// default, which has no in-flight job state to flag.
+    async fn abort_compilation_job(
+        &self,
+        _tenant_id: TenantId,
+        _program_id: ProgramId,
+    ) -> Result<(), DBError> {
+        Ok(())
+    }
+
+    /// Claims a pending job. A backend that overrides
+    /// [`Self::claim_next_compilation_job`] with a single
+    /// `UPDATE ... WHERE id = (SELECT ... FOR UPDATE SKIP LOCKED) RETURNING`
+    /// statement makes this atomic, so concurrent workers never claim the
+    /// same row; under the default, which only calls [`Self::next_job`], no
+    /// claim is recorded and concurrent callers can receive the same job.
+    /// Ergonomic alias for [`Self::claim_next_compilation_job`] under the
+    /// name the compiler service's acquire/renew/reclaim call sites expect.
+    async fn acquire_job(
+        &self,
+        worker_id: WorkerId,
+        lease: Duration,
+    ) -> Result<Option<(TenantId, ProgramId, Version)>, DBError> {
+        self.claim_next_compilation_job(worker_id, lease).await
+    }
+
+    /// Heartbeat called periodically by the worker holding `program_id`'s
+    /// lease. Alias for [`Self::renew_compilation_job_lease`].
+    async fn renew_job_lease(
+        &self,
+        worker_id: WorkerId,
+        program_id: ProgramId,
+        expected_version: Version,
+        lease: Duration,
+    ) -> Result<(), DBError> {
+        self.renew_compilation_job_lease(worker_id, program_id, expected_version, lease)
+            .await
+    }
+
+    /// Run by a reaper task: resets any in-progress job whose
+    /// `lease_expires_at` has passed back to `Pending`. This does not guard
+    /// [`Self::set_program_schema`], which calls `update_program` with no
+    /// expected version, so a stale worker's later schema write is not
+    /// rejected by it.
+    /// Alias for [`Self::reclaim_expired_compilation_leases`].
+    async fn reclaim_expired_jobs(&self) -> Result<Vec<(TenantId, ProgramId)>, DBError> {
+        self.reclaim_expired_compilation_leases().await
+    }
+
     /// Version the configuration for a pipeline.
     ///
     /// Returns the revision number for that snapshot.
@@ -256,6 +613,29 @@ pub(crate) trait Storage {
 
     async fn list_pipelines(&self, tenant_id: TenantId) -> Result<Vec<Pipeline>, DBError>;
 
+    /// Keyset-paginated variant of [`Self::list_pipelines`]: returns
+    /// pipelines with id greater than `after` (`None` to start from the
+    /// beginning), ordered by id, up to `limit` rows, plus the id of the
+    /// last row returned (`None` once the tenant's pipelines are exhausted)
+    /// to pass as `after` on the next call.
+    ///
+    /// Default implementation: paginates [`Self::list_pipelines`] in
+    /// memory, same caveat as [`Self::list_programs_page`]'s default.
+    async fn list_pipelines_page(
+        &self,
+        tenant_id: TenantId,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> Result<(Vec<Pipeline>, Option<Uuid>), DBError> {
+        let pipelines = self.list_pipelines(tenant_id).await?;
+        Ok(paginate_by_id(
+            pipelines,
+            |p| id_as_uuid(&p.pipeline_id),
+            after,
+            limit,
+        ))
+    }
+
     /// Create a new connector.
     async fn new_connector(
         &self,
@@ -269,6 +649,29 @@ pub(crate) trait Storage {
     /// Retrieve connectors list from the DB.
     async fn list_connectors(&self, tenant_id: TenantId) -> Result<Vec<ConnectorDescr>, DBError>;
 
+    /// Keyset-paginated variant of [`Self::list_connectors`]: returns
+    /// connectors with id greater than `after` (`None` to start from the
+    /// beginning), ordered by id, up to `limit` rows, plus the id of the
+    /// last row returned (`None` once the tenant's connectors are
+    /// exhausted) to pass as `after` on the next call.
+    ///
+    /// Default implementation: paginates [`Self::list_connectors`] in
+    /// memory, same caveat as [`Self::list_programs_page`]'s default.
+    async fn list_connectors_page(
+        &self,
+        tenant_id: TenantId,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> Result<(Vec<ConnectorDescr>, Option<Uuid>), DBError> {
+        let connectors = self.list_connectors(tenant_id).await?;
+        Ok(paginate_by_id(
+            connectors,
+            |c| id_as_uuid(&c.connector_id),
+            after,
+            limit,
+        ))
+    }
+
     /// Retrieve connector descriptor for the given `connector_id`.
     async fn get_connector_by_id(
         &self,
@@ -295,14 +698,37 @@ pub(crate) trait Storage {
         config: &Option<ConnectorConfig>,
     ) -> Result<(), DBError>;
 
+    /// Delete connector from the database, same as
+    /// [`Self::delete_connector_with_mode`] under [`DeleteMode::Cascade`],
+    /// discarding the [`DeleteResult`]. This will detach the connector from
+    /// all pipelines that reference it. Kept for source compatibility with
+    /// callers that predate [`DeleteMode`]; those callers relied on the
+    /// original cascading behavior, so this default preserves it rather
+    /// than silently switching to [`DeleteMode::Restrict`].
+    async fn delete_connector(
+        &self,
+        tenant_id: TenantId,
+        connector_id: ConnectorId,
+    ) -> Result<(), DBError> {
+        self.delete_connector_with_mode(tenant_id, connector_id, DeleteMode::Cascade)
+            .await?;
+        Ok(())
+    }
+
     /// Delete connector from the database.
     ///
-    /// This will delete all connector configs and pipelines.
-    async fn delete_connector(
+    /// Under [`DeleteMode::Restrict`], fails with
+    /// [`DBError::DependenciesExist`] if any pipeline still has this
+    /// connector attached. Under [`DeleteMode::Cascade`], the connector is
+    /// detached from those pipelines (reported in
+    /// [`DeleteResult::cascaded_connectors`]) rather than deleting the
+    /// pipelines themselves.
+    async fn delete_connector_with_mode(
         &self,
         tenant_id: TenantId,
         connector_id: ConnectorId,
-    ) -> Result<(), DBError>;
+        mode: DeleteMode,
+    ) -> Result<DeleteResult, DBError>;
 
     /// Get a list of API key names
     async fn list_api_keys(&self, tenant_id: TenantId) -> Result<Vec<ApiKeyDescr>, DBError>;
@@ -379,6 +805,29 @@ pub(crate) trait Storage {
     /// Retrieves a list of all services of a tenant.
     async fn list_services(&self, tenant_id: TenantId) -> Result<Vec<ServiceDescr>, DBError>;
 
+    /// Keyset-paginated variant of [`Self::list_services`]: returns
+    /// services with id greater than `after` (`None` to start from the
+    /// beginning), ordered by id, up to `limit` rows, plus the id of the
+    /// last row returned (`None` once the tenant's services are exhausted)
+    /// to pass as `after` on the next call.
+    ///
+    /// Default implementation: paginates [`Self::list_services`] in memory,
+    /// same caveat as [`Self::list_programs_page`]'s default.
+    async fn list_services_page(
+        &self,
+        tenant_id: TenantId,
+        after: Option<Uuid>,
+        limit: usize,
+    ) -> Result<(Vec<ServiceDescr>, Option<Uuid>), DBError> {
+        let services = self.list_services(tenant_id).await?;
+        Ok(paginate_by_id(
+            services,
+            |s| id_as_uuid(&s.service_id),
+            after,
+            limit,
+        ))
+    }
+
     /// Retrieves service descriptor for the given
     /// `service_id`.
     async fn get_service_by_id(
@@ -406,16 +855,172 @@ pub(crate) trait Storage {
         config: &Option<ServiceConfig>,
     ) -> Result<(), DBError>;
 
+    /// Deletes by id the service from the database, same as
+    /// [`Self::delete_service_with_mode`] under [`DeleteMode::Restrict`],
+    /// discarding the [`DeleteResult`] since nothing is cascaded in that
+    /// mode. Kept for source compatibility with callers that predate
+    /// [`DeleteMode`].
+    async fn delete_service(&self, tenant_id: TenantId, service_id: ServiceId) -> Result<(), DBError> {
+        self.delete_service_with_mode(tenant_id, service_id, DeleteMode::Restrict)
+            .await?;
+        Ok(())
+    }
+
     /// Deletes by id the service from the database.
-    /// TODO: what are pre-conditions for successful deletion?
-    /// TODO: what are post-conditions after successful deletion
-    ///       (e.g., cascading)?
-    async fn delete_service(
+    ///
+    /// Under [`DeleteMode::Restrict`], fails with
+    /// [`DBError::DependenciesExist`] if any pipeline still references this
+    /// service. Under [`DeleteMode::Cascade`], those pipelines are deleted
+    /// too and reported in [`DeleteResult::cascaded_pipelines`]. Either way,
+    /// a currently-running pipeline always blocks the delete.
+    async fn delete_service_with_mode(
         &self,
         tenant_id: TenantId,
         service_id: ServiceId,
-    ) -> Result<(), DBError>;
+        mode: DeleteMode,
+    ) -> Result<DeleteResult, DBError>;
+
+    /// Appends an entry to the tenant's audit log. Implementors that back
+    /// [`Self::new_program`], [`Self::update_pipeline`],
+    /// [`Self::delete_connector_with_mode`], `store_api_key_hash`, etc. with
+    /// durable audit trails should call this after the underlying change is
+    /// applied, ideally from the same transaction, so the audit row and the
+    /// mutation it describes commit together. The default body is a no-op,
+    /// so an implementor that doesn't care about auditing can ignore this
+    /// method entirely; [`Self::list_audit_events`] on such a backend just
+    /// always returns an empty log.
+    async fn record_audit_event(
+        &self,
+        _tenant_id: TenantId,
+        _actor: AuditActor,
+        _action: AuditAction,
+        _object_id: Uuid,
+        _before: Option<Json>,
+        _after: Option<Json>,
+    ) -> Result<(), DBError> {
+        Ok(())
+    }
+
+    /// Retrieves a tenant's audit log, most recent first, filtered by
+    /// `filter` and paginated like [`Self::list_programs_page`]. The default
+    /// always returns an empty page, matching the no-op default of
+    /// [`Self::record_audit_event`].
+    async fn list_audit_events(
+        &self,
+        _tenant_id: TenantId,
+        _filter: AuditEventFilter,
+        _after: Option<Uuid>,
+        _limit: usize,
+    ) -> Result<(Vec<AuditEvent>, Option<Uuid>), DBError> {
+        Ok((Vec::new(), None))
+    }
+
+    /// Records a usage sample for `pipeline_id`, e.g., emitted periodically
+    /// by the runner while the pipeline is running. Samples are additive:
+    /// two calls for overlapping periods double-count, so callers should
+    /// report non-overlapping intervals. The default body is a no-op; a
+    /// backend that doesn't track usage reports zero from
+    /// [`Self::tenant_usage`] as well.
+    async fn record_pipeline_usage(
+        &self,
+        _tenant_id: TenantId,
+        _pipeline_id: PipelineId,
+        _cpu_seconds: f64,
+        _bytes: u64,
+    ) -> Result<(), DBError> {
+        Ok(())
+    }
+
+    /// Sums every `record_pipeline_usage` sample for the tenant whose
+    /// interval overlaps `window`, across all of the tenant's pipelines.
+    /// Intended for billing and quota enforcement. The default always
+    /// reports [`TenantUsage::default`] (all zeros), matching the no-op
+    /// default of [`Self::record_pipeline_usage`].
+    async fn tenant_usage(
+        &self,
+        _tenant_id: TenantId,
+        _window: UsageWindow,
+    ) -> Result<TenantUsage, DBError> {
+        Ok(TenantUsage::default())
+    }
 
     /// Check connectivity to the DB
     async fn check_connection(&self) -> Result<(), DBError>;
+
+    /// Starts a new transaction. The returned [`StorageTransaction`] exposes
+    /// every mutating method on this trait; none of its writes are visible
+    /// to other connections until [`StorageTransaction::commit`] is called,
+    /// and dropping it without committing rolls it back automatically.
+    ///
+    /// Reconciliation loops and pipeline-revision creation should run their
+    /// read-modify-write sequences (e.g. `create_pipeline_revision` followed
+    /// by `update_pipeline_runtime_state`) inside a single transaction so
+    /// version guards and revision snapshots stay consistent across a
+    /// crash.
+    ///
+    /// No default body: unlike the audit/usage methods above, there's no
+    /// backend-agnostic `StorageTransaction` to hand back (it has to hold
+    /// whatever connection/lock the concrete backend uses to make the
+    /// mutating methods atomic), and this trait has no `DBError` variant
+    /// that's safe to construct generically to report "not supported". Every
+    /// implementor of `Storage` must provide this one, same as `new_program`
+    /// and the other primitive single-object mutations.
+    async fn transaction(&self) -> Result<Box<dyn StorageTransaction>, DBError>;
+}
+
+/// A single atomic unit of work spanning several [`Storage`] mutations,
+/// obtained from [`Storage::transaction`]. Exposes the same mutating methods
+/// as [`Storage`] via the supertrait bound, so a read-modify-write sequence
+/// runs against one connection and either all of it applies or none does.
+///
+/// Dropping a `StorageTransaction` without calling [`Self::commit`] rolls it
+/// back; the Postgres implementation does this by holding the underlying
+/// `tokio-postgres` transaction and issuing `ROLLBACK` in its `Drop` impl,
+/// so a panic or early return between mutations never leaks a partial
+/// write.
+///
+/// Like [`Storage::transaction`], `commit` and `rollback` below have no
+/// default body: both are inherently backend-specific (there's no
+/// connection/lock to release, or `DBError` variant to report, that's
+/// meaningful across backends), so every implementor must provide them.
+#[async_trait]
+pub(crate) trait StorageTransaction: Storage {
+    /// Commits all mutations made through this transaction.
+    async fn commit(self: Box<Self>) -> Result<(), DBError>;
+
+    /// Explicitly rolls back all mutations made through this transaction.
+    /// Equivalent to dropping it, but lets the caller observe and handle the
+    /// rollback error instead of losing it.
+    async fn rollback(self: Box<Self>) -> Result<(), DBError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paginate_by_id_walks_pages_in_order() {
+        let ids: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+        let mut sorted_ids = ids.clone();
+        sorted_ids.sort();
+
+        let mut after = None;
+        let mut seen = Vec::new();
+        loop {
+            let (page, next) = paginate_by_id(ids.clone(), |id| *id, after, 2);
+            if page.is_empty() {
+                break;
+            }
+            seen.extend(page);
+            after = next;
+        }
+        assert_eq!(seen, sorted_ids);
+    }
+
+    #[test]
+    fn paginate_by_id_empty_input() {
+        let (page, next) = paginate_by_id::<Uuid>(Vec::new(), |id| *id, None, 10);
+        assert!(page.is_empty());
+        assert_eq!(next, None);
+    }
 }